@@ -1,40 +1,43 @@
+use crate::app::estimate::{Estimate, PictureEstimate};
+use crate::app::filters::Filters;
 use crate::app::format::Format;
-use crate::app::paths::Paths;
-use crate::app::picture::Picture;
+use crate::app::job::Job;
+use crate::app::manifest::{Manifest, ManifestEntry, Output};
+use crate::app::paths::{Paths, is_same_file};
+use crate::app::picture::{Metadata, Picture, Status};
+use crate::app::progress::{BatchProgress, Cancel, Failure, Progress};
 use crate::app::resize::Resize;
 use crate::app::rotate::Rotate;
+use crate::app::scan::Scan;
 use crate::app::sort::Sort;
-use crate::error::TransformationError;
-use rayon::prelude::{IntoParallelRefMutIterator, ParallelBridge, ParallelIterator};
+use crate::app::variants::{Resized, Variants};
+use crate::app::watch::{WatchEntry, WatchEvent};
+use crate::error::{ErrorKind, Stage, TransformationError};
+use rayon::prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 use std::path::{Path, PathBuf};
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Default)]
 pub struct Application {
     pub paths: Paths,
+    pub scan: Scan,
+    pub filters: Filters,
     pub resize: Resize,
     pub format: Format,
     pub rotate: Rotate,
+    pub variants: Variants,
+    pub write_manifest: bool,
     pub sort: Sort,
     pub pictures: Vec<Picture>,
-    pub errors: Vec<TransformationError>,
+    pub errors: Vec<Failure>,
+    /// Why the manifest of the last batch could not be written, kept apart as no picture is to retry for it.
+    pub manifest_error: Option<TransformationError>,
     pub is_in_process: bool,
     pub is_processed: bool,
-}
-
-impl Default for Application {
-    fn default() -> Self {
-        Self {
-            paths: Paths::default(),
-            resize: Resize::default(),
-            format: Format::default(),
-            rotate: Rotate::default(),
-            sort: Sort::default(),
-            pictures: Vec::new(),
-            errors: Vec::new(),
-            is_in_process: false,
-            is_processed: false,
-        }
-    }
+    pub progress: BatchProgress,
+    pub is_watching: bool,
+    pub watch_log: Vec<WatchEntry>,
+    /// Picture shown by the preview, kept by path so sorting does not change it.
+    pub focused: Option<PathBuf>,
 }
 
 impl Application {
@@ -51,59 +54,224 @@ impl Application {
         }
     }
 
+    pub fn rescan(&mut self) {
+        let _ = self.fetch_pictures();
+    }
+
     pub fn set_destination_path(&mut self, path: Option<PathBuf>) {
         if let Some(path) = path {
             self.paths.destination = path;
         }
     }
 
-    pub async fn transform(&mut self) -> Result<(), std::io::Error> {
-        let resize = &self.resize;
-        let rotate = &self.rotate;
-        let format = &self.format;
-        let destination = self.paths.destination.to_owned();
+    /// The settings along with the selected pictures only, what a batch or an estimate run in the background reads.
+    /// The log, the errors and the state of the interface are left out.
+    pub fn batch(&self) -> Self {
+        Self {
+            paths: self.paths.clone(),
+            scan: self.scan.clone(),
+            filters: self.filters.clone(),
+            resize: self.resize.clone(),
+            format: self.format.clone(),
+            rotate: self.rotate.clone(),
+            variants: self.variants.clone(),
+            write_manifest: self.write_manifest,
+            pictures: self.pictures.iter().filter(|picture| picture.is_selected).cloned().collect(),
+            ..Self::default()
+        }
+    }
 
-        self.pictures.par_iter_mut().for_each(|picture| {
-            if !picture.is_selected {
-                return;
+    pub async fn transform(&mut self) -> Result<(), TransformationError> {
+        self.transform_with_progress(&Cancel::default(), |_| {}).await
+    }
+
+    /// Transforms the selected pictures, calling `on_progress` as each one starts and finishes.
+    /// Once `cancel` is set the remaining pictures are skipped, as is the one being encoded.
+    pub async fn transform_with_progress<F>(&mut self, cancel: &Cancel, on_progress: F) -> Result<(), TransformationError>
+    where
+        F: Fn(Progress) + Sync,
+    {
+        let mut pictures = std::mem::take(&mut self.pictures);
+
+        let results: Vec<Result<ManifestEntry, Failure>> = pictures
+            .par_iter_mut()
+            .filter(|picture| picture.is_selected)
+            .map(|picture| {
+                let result = cancel.check(Stage::Load).and_then(|_| {
+                    picture.status = Status::Processing;
+                    on_progress(Progress { path: picture.path.clone(), status: Status::Processing, outputs: Vec::new() });
+                    self.transform_picture(picture, cancel)
+                });
+                picture.status = match &result {
+                    Ok(_) => Status::Done,
+                    Err(err) if err.is_cancelled() => Status::Skipped,
+                    Err(err) => Status::Failed(err.to_string()),
+                };
+                picture.outputs = result.clone().unwrap_or_default();
+                on_progress(Progress {
+                    path: picture.path.clone(),
+                    status: picture.status.clone(),
+                    outputs: picture.outputs.clone(),
+                });
+
+                result
+                    .map(|outputs| ManifestEntry::new(picture, outputs))
+                    .map_err(|error| Failure { name: picture.get_name(), error })
+            })
+            .collect();
+
+        self.pictures = pictures;
+
+        let mut written = Manifest::default();
+        self.errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(entry) => written.pictures.push(entry),
+                Err(failure) if failure.error.is_cancelled() => {}
+                Err(failure) => self.errors.push(failure),
             }
+        }
+
+        if self.write_manifest {
+            let destination = &self.paths.destination;
+            let mut manifest = Manifest::read(destination);
+            manifest.merge(written);
+            manifest
+                .write(destination)
+                .map_err(|e| TransformationError::new(Stage::Write, e).with_output(&destination.join("manifest.json")))?;
+        }
+
+        Ok(())
+    }
 
-            picture.is_in_process = true;
+    /// Decodes the picture once and writes every variant, returning the written files.
+    /// Cancelling is checked between the steps and before each encode, an encode already started runs to its end.
+    /// When cancelled or failing midway the files already written for the picture are removed.
+    pub(crate) fn transform_picture(&self, picture: &Picture, cancel: &Cancel) -> Result<Vec<Output>, TransformationError> {
+        let (resized, formats) = self.render(picture, cancel)?;
+        // Without reformating, prefix or suffix, a destination that is the source folder would replace the pictures.
+        for (_, suffix) in &resized {
+            let path = picture.name.build_variant(&self.paths.destination, suffix);
+            for output in formats.iter().filter_map(|format| format.get_output(&path)) {
+                if is_same_file(&output, &picture.path) {
+                    return Err(TransformationError::new(Stage::Write, ErrorKind::Overwrite)
+                        .with_input(&picture.path)
+                        .with_output(&output));
+                }
+            }
+        }
 
-            if let Ok(mut image) = picture.load() {
-                resize.apply(&mut image);
+        let mut outputs: Vec<Output> = Vec::new();
+        let write = || -> Result<(), TransformationError> {
+            for (image, suffix) in resized {
+                let path = picture.name.build_variant(&self.paths.destination, &suffix);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| TransformationError::new(Stage::Write, e).with_output(parent))?;
+                }
 
-                rotate.apply(&mut image, picture.metadata.rotation);
+                for format in &formats {
+                    // Fitting a max size may downscale the image, each format starts from the full size one.
+                    let mut image = image.clone();
+                    let (file, encoded) = format.apply(&mut image, path.clone(), cancel)?;
+                    outputs.push(Output::new(file, &image, &self.paths.destination, &encoded));
+                }
+            }
+            Ok(())
+        };
 
-                let path = picture.name.build(&destination);
-                if let Err(e) = format.apply(&mut image, path) {
-                    println!("Could not format : {}", picture.get_name());
-                    println!("{}", e);
+        match write() {
+            Ok(()) => Ok(outputs),
+            Err(err) => {
+                for output in &outputs {
+                    let _ = std::fs::remove_file(&output.file);
                 }
-            } else {
-                println!("Could not get image : {}", picture.get_name());
+                Err(err.with_input(&picture.path))
             }
+        }
+    }
 
-            picture.is_in_process = false;
-            picture.is_processed = true;
-        });
+    /// Encodes the selected pictures in memory, or a sample of them for large batches, to project the weight of the outputs.
+    pub fn estimate(&self) -> Estimate {
+        let selected: Vec<&Picture> = self.pictures.iter().filter(|picture| picture.is_selected).collect();
+        let results: Vec<Result<PictureEstimate, Failure>> = Estimate::sample(&selected)
+            .into_par_iter()
+            .map(|picture| {
+                let projected = self
+                    .estimate_picture(picture)
+                    .map_err(|error| Failure { name: picture.get_name(), error })?;
+                Ok(PictureEstimate { name: picture.get_name(), weight: picture.metadata.weight, projected })
+            })
+            .collect();
 
-        Ok(())
+        let mut estimate = Estimate {
+            total: selected.len(),
+            total_weight: selected.iter().map(|picture| picture.metadata.weight).sum(),
+            ..Estimate::default()
+        };
+        for result in results {
+            match result {
+                Ok(picture) => estimate.pictures.push(picture),
+                Err(failure) => estimate.failures.push(failure),
+            }
+        }
+        estimate
     }
 
+    fn estimate_picture(&self, picture: &Picture) -> Result<u64, TransformationError> {
+        let cancel = Cancel::default();
+        let (resized, formats) = self.render(picture, &cancel)?;
+        let mut projected = 0;
+        for (image, _) in &resized {
+            for format in &formats {
+                let encoded = format.encode(&mut image.clone(), &cancel).map_err(|e| e.with_input(&picture.path))?;
+                projected += encoded.bytes.len() as u64;
+            }
+        }
+        Ok(projected)
+    }
+
+    /// The picture at `index` and what its output is encoded from, for the comparison viewer.
+    pub fn compare_job(&self, index: usize) -> (Picture, Job) {
+        let picture = &self.pictures[index];
+        (picture.clone(), Job::new(self, picture))
+    }
+
+    /// Focuses the picture at `index`, or clears the focus when it already has it.
+    pub fn focus(&mut self, index: usize) {
+        let path = &self.pictures[index].path;
+        self.focused = match &self.focused {
+            Some(focused) if focused == path => None,
+            _ => Some(path.clone()),
+        };
+    }
+
+    pub fn get_focused(&self) -> Option<&Picture> {
+        let focused = self.focused.as_ref()?;
+        self.pictures.iter().find(|picture| &picture.path == focused)
+    }
+
+    /// What the preview of the focused picture is encoded from, `None` when no picture has the focus.
+    pub fn preview_job(&self) -> Option<Job> {
+        self.get_focused().map(|picture| Job::new(self, picture))
+    }
+
+    /// Decodes, orients and resizes the picture at the current settings, see [`Job::render`].
+    pub(crate) fn render(&self, picture: &Picture, cancel: &Cancel) -> Result<(Resized, Vec<Format>), TransformationError> {
+        Job::new(self, picture).render(cancel)
+    }
 
     fn fetch_pictures(&mut self) -> Result<(), std::io::Error> {
-        let path = &self.paths.source;
-
-        let pictures = path
-            .read_dir()?
-            .par_bridge()
-            .filter_map(|result| if let Ok(val) = result {
-                let path = val.path();
-                if Self::is_image(&path) {
-                    Some(Picture::new(path))
-                } else { None }
-            } else { None })
+        let root = &self.paths.source;
+        let matcher = self.filters
+            .matcher()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+        let pictures = self.scan
+            .files(root, &self.paths.destination)?
+            .into_par_iter()
+            .filter(|path| Self::is_image(path) && matcher.is_match(path.strip_prefix(root).unwrap_or(path)))
+            // Files removed since they were listed are left out.
+            .filter_map(|path| Picture::new(path, root).ok())
             .collect();
 
         self.pictures = pictures;
@@ -111,7 +279,7 @@ impl Application {
 
         Ok(())
     }
-    fn is_image(path: &Path) -> bool {
+    pub(crate) fn is_image(path: &Path) -> bool {
         match path.extension() {
             Some(ext) => {
                 let ext = ext.to_str().unwrap_or_default().to_lowercase();
@@ -121,6 +289,104 @@ impl Application {
         }
     }
 
+    /// Reflects a picture processed by the watch in the grid and the log.
+    /// A picture already listed only gets its status and file metadata, keeping its selection, overrides and thumbnail.
+    pub fn on_watch_event(&mut self, event: WatchEvent) {
+        self.watch_log.push(WatchEntry::from(&event));
+        let picture = match event {
+            WatchEvent::Processed(picture) => Picture { status: Status::Done, ..picture },
+            WatchEvent::Failed(picture, err) => Picture { status: Status::Failed(err.to_string()), ..picture },
+        };
+
+        match self.pictures.iter_mut().find(|p| p.path == picture.path) {
+            Some(existing) => {
+                existing.status = picture.status;
+                // A file that could not be read comes without metadata, the listed one is the best known.
+                if picture.metadata != Metadata::default() {
+                    // The thumbnail of an edited file is stale, it is generated again once cleared.
+                    if picture.metadata.modified != existing.metadata.modified {
+                        existing.thumbnail = None;
+                    }
+                    existing.metadata = picture.metadata;
+                }
+            }
+            None => {
+                self.pictures.push(picture);
+                self.sort_pictures();
+            }
+        }
+    }
+
+    /// Queues the selected pictures before a batch starts.
+    pub fn start_batch(&mut self) {
+        let mut total = 0;
+        for picture in self.pictures.iter_mut() {
+            picture.status = if picture.is_selected {
+                total += 1;
+                Status::Queued
+            } else {
+                Status::Idle
+            };
+        }
+        self.progress = BatchProgress::start(total);
+    }
+
+    /// Reflects a progress sent by the transformation on its picture and the counts of the batch.
+    pub fn on_progress(&mut self, progress: Progress) {
+        match progress.status {
+            Status::Done => self.progress.done += 1,
+            Status::Failed(_) => self.progress.failed += 1,
+            Status::Skipped => self.progress.skipped += 1,
+            _ => {}
+        }
+        if let Some(picture) = self.pictures.iter_mut().find(|p| p.path == progress.path) {
+            picture.status = progress.status;
+            picture.outputs = progress.outputs;
+        }
+    }
+
+    /// Pictures the grid has no thumbnail for yet.
+    pub fn without_thumbnail(&self) -> Vec<Picture> {
+        self.pictures.iter().filter(|picture| picture.thumbnail.is_none()).cloned().collect()
+    }
+
+    pub fn on_thumbnail(&mut self, path: &Path, thumbnail: PathBuf) {
+        if let Some(picture) = self.pictures.iter_mut().find(|p| p.path == path) {
+            picture.thumbnail = Some(thumbnail);
+        }
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.pictures.iter().any(|picture| matches!(picture.status, Status::Failed(_)))
+    }
+
+    /// Selects only the pictures the last batch failed on, so running it again retries them.
+    /// The selection is kept when none did.
+    pub fn select_failed(&mut self) {
+        if !self.has_failed() {
+            return;
+        }
+        for picture in self.pictures.iter_mut() {
+            picture.is_selected = matches!(picture.status, Status::Failed(_));
+        }
+    }
+
+    /// Settings of the picture at `index`, starting from the batch settings the first time they are changed.
+    pub fn resize_override(&mut self, index: usize) -> &mut Resize {
+        let resize = &self.resize;
+        self.pictures[index].overrides.resize.get_or_insert_with(|| resize.clone())
+    }
+
+    pub fn format_override(&mut self, index: usize) -> &mut Format {
+        let format = &self.format;
+        self.pictures[index].overrides.format.get_or_insert_with(|| format.clone())
+    }
+
+    pub fn rotate_override(&mut self, index: usize) -> &mut Rotate {
+        let rotate = &self.rotate;
+        self.pictures[index].overrides.rotate.get_or_insert_with(|| rotate.clone())
+    }
+
     pub fn select_all(&mut self) {
         self.pictures.iter_mut().for_each(|p| p.is_selected = true);
     }
@@ -131,4 +397,90 @@ impl Application {
     pub fn is_all_selected(&self) -> bool {
         self.pictures.iter().all(|p| p.is_selected)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picture(name: &str, status: Status, is_selected: bool) -> Picture {
+        Picture { path: PathBuf::from(name), status, is_selected, ..Default::default() }
+    }
+
+    #[test]
+    fn selects_the_failed_pictures() {
+        let mut app = Application {
+            pictures: vec![picture("a.png", Status::Failed("load".to_string()), false), picture("b.png", Status::Idle, true)],
+            ..Default::default()
+        };
+        app.select_failed();
+        let selected = app.pictures.iter().map(|picture| picture.is_selected).collect::<Vec<_>>();
+        assert_eq!(selected, [true, false]);
+    }
+
+    #[test]
+    fn keeps_the_selection_when_none_failed() {
+        let mut app = Application {
+            pictures: vec![picture("a.png", Status::Idle, false), picture("b.png", Status::Idle, true)],
+            manifest_error: Some(TransformationError::new(Stage::Write, ErrorKind::NoFormat)),
+            ..Default::default()
+        };
+        app.select_failed();
+        let selected = app.pictures.iter().map(|picture| picture.is_selected).collect::<Vec<_>>();
+        assert_eq!(selected, [false, true]);
+    }
+
+    #[test]
+    fn batches_the_selected_pictures_only() {
+        let app = Application {
+            pictures: vec![picture("a.png", Status::Idle, true), picture("b.png", Status::Idle, false)],
+            watch_log: vec![WatchEntry { name: "a.png".to_string(), message: String::new(), is_error: false }],
+            write_manifest: true,
+            ..Default::default()
+        };
+        let batch = app.batch();
+        assert_eq!(batch.pictures.len(), 1);
+        assert_eq!(batch.pictures[0].path, PathBuf::from("a.png"));
+        assert!(batch.write_manifest);
+        assert!(batch.watch_log.is_empty());
+    }
+
+    #[test]
+    fn watch_events_keep_the_picture_settings() {
+        let mut listed = picture("a.png", Status::Idle, false);
+        listed.overrides.rotate = Some(Rotate::default());
+        listed.thumbnail = Some(PathBuf::from("thumb.webp"));
+        listed.metadata.weight = 10;
+        let mut app = Application { pictures: vec![listed], ..Default::default() };
+
+        let mut processed = picture("a.png", Status::Idle, true);
+        processed.metadata.weight = 20;
+        app.on_watch_event(WatchEvent::Processed(processed));
+        let updated = &app.pictures[0];
+        assert_eq!(updated.status, Status::Done);
+        assert_eq!(updated.metadata.weight, 20);
+        assert!(!updated.is_selected);
+        assert!(updated.overrides.rotate.is_some());
+        assert_eq!(updated.thumbnail, Some(PathBuf::from("thumb.webp")));
+
+        let unreadable = picture("a.png", Status::Idle, true);
+        app.on_watch_event(WatchEvent::Failed(unreadable, TransformationError::new(Stage::Load, ErrorKind::NoFormat)));
+        assert!(matches!(app.pictures[0].status, Status::Failed(_)));
+        assert_eq!(app.pictures[0].metadata.weight, 20);
+        assert_eq!(app.watch_log.len(), 2);
+    }
+
+    #[test]
+    fn watch_events_refresh_the_thumbnail_of_edited_files() {
+        let mut listed = picture("a.png", Status::Idle, true);
+        listed.thumbnail = Some(PathBuf::from("thumb.webp"));
+        let mut app = Application { pictures: vec![listed], ..Default::default() };
+
+        let mut edited = picture("a.png", Status::Idle, true);
+        edited.metadata.modified = Some(std::time::SystemTime::now());
+        app.on_watch_event(WatchEvent::Processed(edited));
+        assert_eq!(app.pictures[0].thumbnail, None);
+
+        app.on_watch_event(WatchEvent::Processed(picture("b.png", Status::Idle, true)));
+        assert_eq!(app.pictures.len(), 2);
+    }
+}
@@ -0,0 +1,317 @@
+use image::{DynamicImage, ImageBuffer, Rgba};
+use rav1e::prelude::PixelRange;
+use ravif::{AlphaColorMode, BitDepth, Encoder, Img, MatrixCoefficients};
+use rgb::FromSlice;
+use serde::{Deserialize, Serialize};
+use crate::app::format::Speed;
+use crate::error::TransformationError;
+#[cfg(feature = "gui")]
+use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
+
+/// Weights of red, green and blue in the brightness, the matrix ravif uses for its YCbCr.
+const BT601: [f32; 3] = [0.2990, 0.5870, 0.1140];
+const TEN_BIT_MAX: f32 = 1023.0;
+
+type Rgba16Image = ImageBuffer<Rgba<u16>, Vec<u16>>;
+
+/// Settings of the AVIF encoder, besides the quality and speed shared with the other formats.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Avif {
+    /// Quality of the alpha channel, encoded on its own, from 1 to 100.
+    pub alpha_quality: u8,
+    pub alpha_mode: AlphaMode,
+    pub color_model: ColorModel,
+    /// 10-bit colours, smoother gradients for about the same size. 16-bit sources keep their precision only with it.
+    pub ten_bit: bool,
+    /// Threads of a single encode, 0 using all of them.
+    pub threads: u8,
+}
+
+/// What is stored in the colours of the transparent pixels.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlphaMode {
+    /// Colours kept as they are, even where nothing shows.
+    Dirty,
+    /// Colours of the fully transparent pixels smoothed away, they compress better.
+    #[default]
+    Clean,
+    /// Colours multiplied by their alpha, not every decoder supports it.
+    Premultiplied,
+}
+
+/// How the colours are stored in the file.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorModel {
+    /// Brightness and two colour differences, the best fit for photos.
+    #[default]
+    YCbCr,
+    /// Red, green and blue as they are, larger but exact for pictures relying on them, such as anaglyphs.
+    Rgb,
+}
+
+impl Default for Avif {
+    fn default() -> Self {
+        Self { alpha_quality: 80, alpha_mode: AlphaMode::default(), color_model: ColorModel::default(), ten_bit: true, threads: 0 }
+    }
+}
+
+impl Avif {
+    pub fn set_alpha_mode(&mut self, value: String) {
+        self.alpha_mode = match value.as_str() {
+            "dirty" => AlphaMode::Dirty,
+            "premultiplied" => AlphaMode::Premultiplied,
+            _ => AlphaMode::Clean,
+        }
+    }
+
+    pub fn set_color_model(&mut self, value: String) {
+        self.color_model = match value.as_str() {
+            "rgb" => ColorModel::Rgb,
+            _ => ColorModel::YCbCr,
+        }
+    }
+
+    /// Encodes `image`, leaving the alpha plane out when every pixel is opaque.
+    pub fn encode(&self, image: &DynamicImage, quality: u8, speed: &Speed) -> Result<Vec<u8>, TransformationError> {
+        let encoder = Encoder::new()
+            .with_speed(speed.value.clamp(1, 10))
+            .with_quality(quality.clamp(1, 100) as f32)
+            .with_alpha_quality(self.alpha_quality.clamp(1, 100) as f32)
+            .with_alpha_color_mode(self.alpha_mode.to_ravif())
+            .with_internal_color_model(self.color_model.to_ravif())
+            .with_bit_depth(if self.ten_bit { BitDepth::Ten } else { BitDepth::Eight })
+            .with_num_threads((self.threads > 0).then_some(self.threads as usize));
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let color = image.color();
+        let is_deep = color.bits_per_pixel() / color.channel_count() as u16 > 8;
+
+        let avif = if is_deep && self.ten_bit {
+            let mut rgba = image.to_rgba16();
+            let has_alpha = rgba.pixels().any(|pixel| pixel[3] < u16::MAX);
+            // ravif only cleans the colours of the pictures it converts itself.
+            if has_alpha && self.alpha_mode == AlphaMode::Clean {
+                rgba = clean_transparent(&rgba);
+            }
+            let premultiplied = has_alpha && self.alpha_mode == AlphaMode::Premultiplied;
+            let planes = rgba.pixels().map(|pixel| self.color_model.to_planes(pixel.0, premultiplied));
+            let alpha = has_alpha.then(|| rgba.pixels().map(|pixel| to_ten_bit(pixel[3])));
+            encoder.encode_raw_planes_10_bit(width, height, planes, alpha, PixelRange::Full, self.color_model.to_matrix())?
+        } else if color.has_alpha() && !is_opaque(image) {
+            let rgba = image.to_rgba8();
+            encoder.encode_rgba(Img::new(rgba.as_raw().as_rgba(), width, height))?
+        } else {
+            let rgb = image.to_rgb8();
+            encoder.encode_rgb(Img::new(rgb.as_raw().as_rgb(), width, height))?
+        };
+        Ok(avif.avif_file)
+    }
+}
+
+impl AlphaMode {
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            AlphaMode::Dirty => "dirty",
+            AlphaMode::Clean => "clean",
+            AlphaMode::Premultiplied => "premultiplied",
+        }
+    }
+
+    fn to_ravif(self) -> AlphaColorMode {
+        match self {
+            AlphaMode::Dirty => AlphaColorMode::UnassociatedDirty,
+            AlphaMode::Clean => AlphaColorMode::UnassociatedClean,
+            AlphaMode::Premultiplied => AlphaColorMode::Premultiplied,
+        }
+    }
+}
+
+impl ColorModel {
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            ColorModel::YCbCr => "ycbcr",
+            ColorModel::Rgb => "rgb",
+        }
+    }
+
+    fn to_ravif(self) -> ravif::ColorModel {
+        match self {
+            ColorModel::YCbCr => ravif::ColorModel::YCbCr,
+            ColorModel::Rgb => ravif::ColorModel::RGB,
+        }
+    }
+
+    fn to_matrix(self) -> MatrixCoefficients {
+        match self {
+            ColorModel::YCbCr => MatrixCoefficients::BT601,
+            ColorModel::Rgb => MatrixCoefficients::Identity,
+        }
+    }
+
+    /// The 10-bit planes of a 16-bit pixel, converted the way ravif converts 8-bit ones.
+    fn to_planes(self, [red, green, blue, alpha]: [u16; 4], premultiplied: bool) -> [u16; 3] {
+        let scale = TEN_BIT_MAX / u16::MAX as f32 * if premultiplied { alpha as f32 / u16::MAX as f32 } else { 1.0 };
+        let [red, green, blue] = [red, green, blue].map(|channel| channel as f32 * scale);
+        let planes = match self {
+            ColorModel::YCbCr => {
+                let shift = (TEN_BIT_MAX * 0.5).round();
+                let y = BT601[0] * red + BT601[1] * green + BT601[2] * blue;
+                let cb = (blue - y) * 0.5 / (1.0 - BT601[2]) + shift;
+                let cr = (red - y) * 0.5 / (1.0 - BT601[0]) + shift;
+                [y, cb, cr]
+            }
+            ColorModel::Rgb => [green, blue, red],
+        };
+        planes.map(|plane| plane.round().clamp(0.0, TEN_BIT_MAX) as u16)
+    }
+}
+
+fn to_ten_bit(value: u16) -> u16 {
+    ((value as u32 * TEN_BIT_MAX as u32 + u16::MAX as u32 / 2) / u16::MAX as u32) as u16
+}
+
+/// Colours of the fully transparent pixels replaced, the way ravif cleans 8-bit pictures.
+/// Those next to visible pixels take their colour, weighted by alpha, so the edges compression blurs stay out of sight,
+/// the others the mean colour of the visible pixels.
+fn clean_transparent(rgba: &Rgba16Image) -> Rgba16Image {
+    let mean = weighted_mean(rgba.pixels());
+    let (width, height) = rgba.dimensions();
+    Rgba16Image::from_fn(width, height, |x, y| {
+        let pixel = *rgba.get_pixel(x, y);
+        if pixel[3] > 0 {
+            return pixel;
+        }
+        let neighbours = (y.saturating_sub(1)..(y + 2).min(height))
+            .flat_map(|y| (x.saturating_sub(1)..(x + 2).min(width)).map(move |x| (x, y)))
+            .map(|(x, y)| rgba.get_pixel(x, y));
+        let [red, green, blue] = weighted_mean(neighbours).unwrap_or(mean.unwrap_or_default());
+        Rgba([red, green, blue, 0])
+    })
+}
+
+/// Mean colour of `pixels` weighted by their alpha, `None` when none of them shows.
+fn weighted_mean<'a>(pixels: impl Iterator<Item = &'a Rgba<u16>>) -> Option<[u16; 3]> {
+    let mut sum = [0_u64; 3];
+    let mut weight = 0_u64;
+    for pixel in pixels {
+        let alpha = pixel[3] as u64;
+        for channel in 0..3 {
+            sum[channel] += pixel[channel] as u64 * alpha;
+        }
+        weight += alpha;
+    }
+    (weight > 0).then(|| sum.map(|channel| (channel / weight) as u16))
+}
+
+fn is_opaque(image: &DynamicImage) -> bool {
+    match image {
+        DynamicImage::ImageLumaA8(image) => image.pixels().all(|pixel| pixel[1] == u8::MAX),
+        DynamicImage::ImageRgba8(image) => image.pixels().all(|pixel| pixel[3] == u8::MAX),
+        _ => image.to_rgba16().pixels().all(|pixel| pixel[3] == u16::MAX),
+    }
+}
+
+#[cfg(feature = "gui")]
+const ALPHA_OPTIONS: [(&str, &str); 3] = [("clean", "Clean transparent colours"), ("dirty", "Keep transparent colours"), ("premultiplied", "Premultiplied")];
+#[cfg(feature = "gui")]
+impl ToHtml for AlphaMode {
+    fn to_html(&self) -> Element {
+        let selected = self.get_key();
+        rsx!{
+            for (value , label) in ALPHA_OPTIONS {
+                option { value, selected: value == selected, {label} }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+const COLOR_OPTIONS: [(&str, &str); 2] = [("ycbcr", "YCbCr"), ("rgb", "RGB")];
+#[cfg(feature = "gui")]
+impl ToHtml for ColorModel {
+    fn to_html(&self) -> Element {
+        let selected = self.get_key();
+        rsx!{
+            for (value , label) in COLOR_OPTIONS {
+                option { value, selected: value == selected, {label} }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u16 = u16::MAX;
+
+    #[test]
+    fn scales_to_ten_bits() {
+        assert_eq!(to_ten_bit(0), 0);
+        assert_eq!(to_ten_bit(MAX), 1023);
+        assert_eq!(to_ten_bit(MAX / 2), 511);
+    }
+
+    #[test]
+    fn lays_the_rgb_planes_out_green_first() {
+        let planes = ColorModel::Rgb.to_planes([MAX, MAX / 2, 0, MAX], false);
+        assert_eq!(planes, [511, 0, 1023]);
+    }
+
+    #[test]
+    fn lays_the_ycbcr_planes_out_luma_first() {
+        assert_eq!(ColorModel::YCbCr.to_planes([0, 0, 0, MAX], false), [0, 512, 512]);
+        assert_eq!(ColorModel::YCbCr.to_planes([MAX, MAX, MAX, MAX], false), [1023, 512, 512]);
+        // Red brings the red difference up and the blue one down, blue the other way around.
+        let [y, cb, cr] = ColorModel::YCbCr.to_planes([MAX, 0, 0, MAX], false);
+        assert_eq!(y, 306);
+        assert!(cb < 512 && cr == 1023, "{} {}", cb, cr);
+        let [y, cb, cr] = ColorModel::YCbCr.to_planes([0, 0, MAX, MAX], false);
+        assert_eq!(y, 117);
+        assert!(cb == 1023 && cr < 512, "{} {}", cb, cr);
+    }
+
+    #[test]
+    fn premultiplies_by_the_alpha() {
+        assert_eq!(ColorModel::Rgb.to_planes([MAX, MAX, MAX, MAX / 2], true), [511, 511, 511]);
+        assert_eq!(ColorModel::Rgb.to_planes([MAX, MAX, MAX, 0], true), [0, 0, 0]);
+        assert_eq!(ColorModel::Rgb.to_planes([MAX, MAX, MAX, 0], false), [1023, 1023, 1023]);
+    }
+
+    #[test]
+    fn cleans_transparent_pixels_with_their_neighbours() {
+        // A red pixel at the left edge, the rest transparent with garbage colours.
+        let rgba = Rgba16Image::from_fn(4, 1, |x, _| if x == 0 { Rgba([MAX, 0, 0, MAX]) } else { Rgba([0, MAX, MAX, 0]) });
+        let cleaned = clean_transparent(&rgba);
+        assert_eq!(*cleaned.get_pixel(0, 0), Rgba([MAX, 0, 0, MAX]));
+        // Next to the red pixel, and far from it where the mean of the visible pixels is the same red.
+        assert_eq!(*cleaned.get_pixel(1, 0), Rgba([MAX, 0, 0, 0]));
+        assert_eq!(*cleaned.get_pixel(3, 0), Rgba([MAX, 0, 0, 0]));
+    }
+
+    #[test]
+    fn weights_the_neighbours_by_alpha() {
+        let rgba = Rgba16Image::from_fn(3, 1, |x, _| match x {
+            0 => Rgba([MAX, 0, 0, MAX]),
+            1 => Rgba([0, 0, 0, 0]),
+            _ => Rgba([0, 0, MAX, MAX / 3]),
+        });
+        let cleaned = clean_transparent(&rgba);
+        let [red, green, blue, alpha] = cleaned.get_pixel(1, 0).0;
+        assert_eq!((green, alpha), (0, 0));
+        assert!(red > blue * 2, "{} {}", red, blue);
+        // Partly transparent pixels keep their colour.
+        assert_eq!(*cleaned.get_pixel(2, 0), Rgba([0, 0, MAX, MAX / 3]));
+    }
+
+    #[test]
+    fn cleans_to_black_without_visible_pixels() {
+        let rgba = Rgba16Image::from_pixel(2, 2, Rgba([MAX, MAX, MAX, 0]));
+        let cleaned = clean_transparent(&rgba);
+        assert!(cleaned.pixels().all(|pixel| *pixel == Rgba([0, 0, 0, 0])));
+    }
+}
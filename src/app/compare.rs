@@ -0,0 +1,87 @@
+use crate::app::format::Encoded;
+use crate::app::job::Job;
+use crate::app::manifest::get_encoding;
+use crate::app::picture::{format_weight, Picture};
+use crate::app::progress::Cancel;
+use crate::error::{ErrorKind, Stage, TransformationError};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::{DynamicImage, ImageFormat};
+use std::io::Cursor;
+
+/// A picture next to its output at the current settings, both ready to be shown by the comparison viewer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comparison {
+    pub name: String,
+    pub original: Side,
+    pub encoded: Side,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Side {
+    pub label: String,
+    /// Path or data URL the webview loads the image from.
+    pub source: String,
+    pub weight: u64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Comparison {
+    pub fn new(picture: &Picture, job: &Job) -> Result<Self, TransformationError> {
+        Ok(Self {
+            name: picture.get_name(),
+            original: Side::original(picture),
+            encoded: Side::encode(job, &Cancel::default())?,
+        })
+    }
+}
+
+impl Side {
+    pub fn original(picture: &Picture) -> Self {
+        Self {
+            label: "Original".to_string(),
+            source: picture.get_path().to_string(),
+            weight: picture.metadata.weight,
+            width: picture.metadata.width as u32,
+            height: picture.metadata.height as u32,
+        }
+    }
+
+    /// Encodes the largest variant of the picture at the first format, nothing is written.
+    pub fn encode(job: &Job, cancel: &Cancel) -> Result<Self, TransformationError> {
+        let (resized, formats) = job.render(cancel)?;
+        let largest = resized.into_iter().max_by_key(|(image, _)| image.width());
+        let (Some((mut image, _)), Some(format)) = (largest, formats.into_iter().next()) else {
+            return Err(TransformationError::new(Stage::Format, ErrorKind::NoFormat).with_input(&job.path));
+        };
+        let encoded = format.encode(&mut image, cancel).map_err(|e| e.with_input(&job.path))?;
+        let image_format = format.image.unwrap_or(ImageFormat::Png);
+
+        Ok(Self {
+            label: format!("{} {}", image_format.extensions_str()[0].to_uppercase(), get_encoding(encoded.quality, encoded.score, encoded.colors))
+                .trim_end()
+                .to_string(),
+            source: to_data_url(&image, &encoded, image_format).map_err(|e| e.with_input(&job.path))?,
+            weight: encoded.bytes.len() as u64,
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+
+    pub fn get_summary(&self) -> String {
+        format!("{} · {} · {}px - {}px", self.label, format_weight(self.weight), self.width, self.height)
+    }
+}
+
+/// The webview cannot show TIFF, those outputs are shown through a lossless PNG copy.
+fn to_data_url(image: &DynamicImage, encoded: &Encoded, format: ImageFormat) -> Result<String, TransformationError> {
+    if format != ImageFormat::Tiff {
+        return Ok(format!("data:{};base64,{}", format.to_mime_type(), STANDARD.encode(&encoded.bytes)));
+    }
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| TransformationError::new(Stage::Format, e))?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png.into_inner())))
+}
@@ -0,0 +1,155 @@
+use crate::app::picture::{format_weight, Picture};
+use crate::app::progress::Failure;
+
+/// Number of pictures encoded when estimating a larger batch, the others are extrapolated from them.
+const SAMPLE: usize = 24;
+
+/// Projected weight of the outputs, computed by encoding in memory without writing anything.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Estimate {
+    pub pictures: Vec<PictureEstimate>,
+    pub failures: Vec<Failure>,
+    /// Number and weight of every selected picture, including the ones left out of the sample.
+    pub total: usize,
+    pub total_weight: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PictureEstimate {
+    pub name: String,
+    pub weight: u64,
+    /// Weight of every variant written for the picture.
+    pub projected: u64,
+}
+
+impl PictureEstimate {
+    pub fn get_summary(&self) -> String {
+        format!("{} -> {} ({})", format_weight(self.weight), format_weight(self.projected), get_savings(self.weight, self.projected))
+    }
+}
+
+impl Estimate {
+    /// Pictures to encode, evenly spread over the batch once it is larger than the sample.
+    pub fn sample<'a>(pictures: &[&'a Picture]) -> Vec<&'a Picture> {
+        if pictures.len() <= SAMPLE {
+            return pictures.to_vec();
+        }
+        (0..SAMPLE).map(|i| pictures[i * pictures.len() / SAMPLE]).collect()
+    }
+
+    pub fn is_sampled(&self) -> bool {
+        self.pictures.len() + self.failures.len() < self.total
+    }
+
+    /// Weight the projection is compared to, the failed pictures being left out unless the batch was sampled.
+    pub fn source_weight(&self) -> u64 {
+        if self.is_sampled() {
+            return self.total_weight;
+        }
+        self.pictures.iter().map(|picture| picture.weight).sum()
+    }
+
+    /// Weight of the outputs of the whole batch, the sample being scaled to it at the same ratio.
+    pub fn projected_weight(&self) -> u64 {
+        let projected: u64 = self.pictures.iter().map(|picture| picture.projected).sum();
+        if !self.is_sampled() {
+            return projected;
+        }
+        let weight: u64 = self.pictures.iter().map(|picture| picture.weight).sum();
+        if weight == 0 {
+            return 0;
+        }
+        (projected as f64 / weight as f64 * self.total_weight as f64) as u64
+    }
+
+    pub fn get_summary(&self) -> String {
+        let weight = self.source_weight();
+        let projected = self.projected_weight();
+        let mut summary = format!(
+            "{} -> {} ({})",
+            format_weight(weight),
+            format_weight(projected),
+            get_savings(weight, projected)
+        );
+        if self.is_sampled() {
+            summary += &format!(", estimated from {} of {} pictures", self.pictures.len(), self.total);
+        }
+        summary
+    }
+}
+
+fn get_savings(weight: u64, projected: u64) -> String {
+    if weight == 0 {
+        return "-".to_string();
+    }
+    let change = (projected as f64 - weight as f64) / weight as f64 * 100.0;
+    format!("{:+.0}%", change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn pictures(count: usize) -> Vec<Picture> {
+        (0..count).map(|i| Picture { path: PathBuf::from(format!("{}.png", i)), ..Default::default() }).collect()
+    }
+
+    fn picture(weight: u64, projected: u64) -> PictureEstimate {
+        PictureEstimate { name: String::new(), weight, projected }
+    }
+
+    #[test]
+    fn samples_every_picture_of_small_batches() {
+        let pictures = pictures(SAMPLE);
+        let selected = pictures.iter().collect::<Vec<_>>();
+        assert_eq!(Estimate::sample(&selected), selected);
+        assert!(Estimate::sample(&[]).is_empty());
+    }
+
+    #[test]
+    fn spreads_the_sample_over_large_batches() {
+        let pictures = pictures(SAMPLE * 10 + 3);
+        let selected = pictures.iter().collect::<Vec<_>>();
+        let sample = Estimate::sample(&selected);
+        assert_eq!(sample.len(), SAMPLE);
+        assert_eq!(sample[0].path, PathBuf::from("0.png"));
+        assert_eq!(sample[1].path, PathBuf::from("10.png"));
+        assert_eq!(sample[SAMPLE - 1].path, PathBuf::from("232.png"));
+    }
+
+    #[test]
+    fn sums_the_weights_of_a_full_estimate() {
+        let estimate = Estimate { pictures: vec![picture(1000, 400), picture(3000, 600)], total: 2, total_weight: 4000, ..Default::default() };
+        assert!(!estimate.is_sampled());
+        assert_eq!(estimate.source_weight(), 4000);
+        assert_eq!(estimate.projected_weight(), 1000);
+        assert_eq!(estimate.get_summary(), format!("{} -> {} (-75%)", format_weight(4000), format_weight(1000)));
+    }
+
+    #[test]
+    fn scales_a_sample_to_the_batch() {
+        let estimate = Estimate { pictures: vec![picture(1000, 500), picture(1000, 300)], total: 20, total_weight: 30_000, ..Default::default() };
+        assert!(estimate.is_sampled());
+        assert_eq!(estimate.source_weight(), 30_000);
+        assert_eq!(estimate.projected_weight(), 12_000);
+        assert!(estimate.get_summary().ends_with("(-60%), estimated from 2 of 20 pictures"));
+    }
+
+    #[test]
+    fn leaves_failures_out_of_the_weights() {
+        let failure = Failure { name: "b.png".to_string(), error: crate::error::TransformationError::cancelled(crate::error::Stage::Load) };
+        let estimate = Estimate { pictures: vec![picture(1000, 500)], failures: vec![failure], total: 2, total_weight: 5000 };
+        assert!(!estimate.is_sampled());
+        assert_eq!(estimate.source_weight(), 1000);
+        assert_eq!(estimate.projected_weight(), 500);
+    }
+
+    #[test]
+    fn projects_nothing_from_empty_pictures() {
+        let estimate = Estimate { pictures: vec![picture(0, 0)], total: 50, total_weight: 1000, ..Default::default() };
+        assert_eq!(estimate.projected_weight(), 0);
+        assert_eq!(get_savings(0, 100), "-");
+        assert_eq!(get_savings(100, 150), "+50%");
+    }
+}
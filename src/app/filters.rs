@@ -0,0 +1,130 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Glob patterns matched against the path of a file relative to the source folder.
+/// Patterns starting with `!` exclude the files they match.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Filters {
+    pub patterns: Vec<String>,
+}
+
+impl Filters {
+    /// Patterns separated by spaces or lines, commas being kept for brace alternatives such as `*.{jpg,png}`.
+    /// A space inside a pattern is written `?` or `[ ]`.
+    pub fn set_patterns(&mut self, value: String) {
+        self.patterns = value.split_whitespace().map(String::from).collect();
+    }
+
+    pub fn get_patterns(&self) -> String {
+        self.patterns.join(" ")
+    }
+
+    pub fn matcher(&self) -> Result<Matcher, globset::Error> {
+        let mut include = GlobSetBuilder::new();
+        let mut exclude = GlobSetBuilder::new();
+        let mut has_include = false;
+
+        for pattern in &self.patterns {
+            match pattern.strip_prefix('!') {
+                Some(pattern) => {
+                    exclude.add(Glob::new(pattern)?);
+                }
+                None => {
+                    include.add(Glob::new(pattern)?);
+                    has_include = true;
+                }
+            }
+        }
+
+        Ok(Matcher {
+            include: if has_include { Some(include.build()?) } else { None },
+            exclude: exclude.build()?,
+        })
+    }
+}
+
+pub struct Matcher {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Matcher {
+    /// Without include patterns every file is picked up unless it is excluded.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let is_included = self.include.as_ref().is_none_or(|include| include.is_match(path));
+        is_included && !self.exclude.is_match(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(patterns: &str) -> Matcher {
+        let mut filters = Filters::default();
+        filters.set_patterns(patterns.to_string());
+        filters.matcher().unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        let mut filters = Filters::default();
+        filters.set_patterns(" *.{jpg,png}\n!**/thumbs/**   raw/* ".to_string());
+        assert_eq!(filters.patterns, ["*.{jpg,png}", "!**/thumbs/**", "raw/*"]);
+        assert_eq!(filters.get_patterns(), "*.{jpg,png} !**/thumbs/** raw/*");
+    }
+
+    #[test]
+    fn matches_everything_without_patterns() {
+        let matcher = matcher("");
+        assert!(matcher.is_match(Path::new("a.png")));
+        assert!(matcher.is_match(Path::new("deep/down/a.jpg")));
+    }
+
+    #[test]
+    fn includes_only_the_matches() {
+        let matcher = matcher("*.png");
+        assert!(matcher.is_match(Path::new("a.png")));
+        assert!(!matcher.is_match(Path::new("a.jpg")));
+    }
+
+    #[test]
+    fn excludes_the_matches() {
+        let matcher = matcher("!**/thumbs/**");
+        assert!(matcher.is_match(Path::new("a.png")));
+        assert!(!matcher.is_match(Path::new("thumbs/a.png")));
+        assert!(!matcher.is_match(Path::new("2024/thumbs/a.png")));
+    }
+
+    #[test]
+    fn exclusion_wins_over_inclusion() {
+        let matcher = matcher("**/*.png !**/draft-*");
+        assert!(matcher.is_match(Path::new("2024/final.png")));
+        assert!(!matcher.is_match(Path::new("2024/draft-1.png")));
+        assert!(!matcher.is_match(Path::new("2024/final.jpg")));
+    }
+
+    #[test]
+    fn matches_brace_alternatives() {
+        let matcher = matcher("{holidays,family}/*.{jpg,png}");
+        assert!(matcher.is_match(Path::new("holidays/a.jpg")));
+        assert!(matcher.is_match(Path::new("family/a.png")));
+        assert!(!matcher.is_match(Path::new("work/a.png")));
+        assert!(!matcher.is_match(Path::new("family/a.webp")));
+    }
+
+    #[test]
+    fn matches_nested_paths() {
+        let matcher = matcher("2024/**/*.jpg");
+        assert!(matcher.is_match(Path::new("2024/a.jpg")));
+        assert!(matcher.is_match(Path::new("2024/05/01/a.jpg")));
+        assert!(!matcher.is_match(Path::new("2023/05/a.jpg")));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let mut filters = Filters::default();
+        filters.set_patterns("a[".to_string());
+        assert!(filters.matcher().is_err());
+    }
+}
@@ -1,35 +1,304 @@
-use std::path::PathBuf;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use image::{DynamicImage, ImageFormat};
+#[cfg(feature = "gui")]
 use crate::components::ToHtml;
+#[cfg(feature = "gui")]
 use dioxus::prelude::*;
-use ravif::{Encoder, Img};
-use rgb::{FromSlice, RGBA8};
-use crate::error::TransformationError;
+use crate::app::avif::Avif;
+use crate::app::jpeg::Jpeg;
+use crate::app::png::Png;
+use crate::app::progress::Cancel;
+use crate::app::webp::Webp;
+use crate::app::resize::ResizeMethod;
+use crate::app::ssim::ssim;
+use crate::error::{ErrorKind, Stage, TransformationError};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, PartialEq, Debug, Default)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Format {
+    #[serde(with = "image_format")]
     pub image : Option<ImageFormat>,
     pub quality : Quality,
-    pub speed : Speed
+    pub speed : Speed,
+    pub max_size : Option<MaxSize>,
+    pub target : Option<Target>,
+    pub jpeg : Jpeg,
+    pub png : Png,
+    pub webp : Webp,
+    pub avif : Avif,
 }
 
-const OPTIONS: [(&str, &str); 6] = [("none", "No reformating"),("png", "PNG"), ("jpg", "JPG"), ("webp", "WEBP"), ("avif", "AVIF"), ("tiff", "TIFF")];
-impl ToHtml for ImageFormat{
+/// Byte budget every output is fitted in, the quality being searched between `min_quality` and the quality setting.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaxSize {
+    pub bytes: u64,
+    pub min_quality: u8,
+    /// Downscales the picture when even `min_quality` does not fit, the only way to fit lossless formats.
+    pub downscale: bool,
+}
+
+/// Perceptual quality every output has to reach, the lowest encoder quality scoring at least `ssim` being picked.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Target {
+    pub ssim: f64,
+}
+
+/// An encoded image, with the quality it was encoded at for the lossy formats and its SSIM when a target was set.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Encoded {
+    pub bytes: Vec<u8>,
+    pub quality: Option<u8>,
+    pub score: Option<f64>,
+    /// Colours of the palette the image was reduced to.
+    pub colors: Option<u16>,
+}
+
+/// Key of the PNG reduced to a palette, stored as PNG with the palette turned on in its settings.
+const PALETTE: &str = "png-palette";
+
+/// Downscaling step applied while an image does not fit its max size.
+const DOWNSCALE: f32 = 0.8;
+const MIN_SIDE: u32 = 16;
+/// Highest quality the encoders take, anything above it is read as this.
+pub const MAX_QUALITY: u8 = 100;
+
+#[cfg(feature = "gui")]
+pub(crate) const OPTIONS: [(&str, &str); 7] = [("none", "No reformating"),("png", "PNG"), ("png-palette", "PNG (lossy palette)"), ("jpg", "JPG"), ("webp", "WEBP"), ("avif", "AVIF"), ("tiff", "TIFF")];
+#[cfg(feature = "gui")]
+impl ToHtml for Format{
     fn to_html(&self) -> Element {
+        let selected = self.get_format();
         rsx!{
             for (value , label) in OPTIONS {
-                option { value, {label} }
+                option { value, selected: value == selected, {label} }
             }
         }
     }
 }
 
 impl Format{
-    pub fn get_default_image_format() -> ImageFormat{
-        ImageFormat::Png
+    pub fn get_format(&self) -> &'static str{
+        if self.is_palette() {
+            return PALETTE;
+        }
+        image_format::to_key(self.image)
     }
     pub fn set_format(&mut self, value: String){
-        self.image = match value.as_str(){
+        self.png.palette = value == PALETTE;
+        self.image = image_format::from_key(if self.png.palette { "png" } else { &value })
+    }
+
+    /// PNG reduced to a palette, lossy like JPG with the quality setting the number of colours.
+    pub fn is_palette(&self) -> bool{
+        self.image == Some(ImageFormat::Png) && self.png.palette
+    }
+
+    /// Sets the max size from a value such as `200 KB` or `1.5MB`, a value without unit being in bytes.
+    /// Anything else, `0` included, turns the max size off.
+    pub fn set_max_size(&mut self, value: String){
+        let bytes = parse_size(&value).filter(|bytes| *bytes > 0);
+        self.max_size = bytes.map(|bytes| MaxSize { bytes, ..self.max_size.clone().unwrap_or_default() });
+    }
+
+    pub fn get_max_size(&self) -> String{
+        self.max_size.as_ref().map(|max_size| display_size(max_size.bytes)).unwrap_or_default()
+    }
+
+    pub fn is_lossy(&self) -> bool{
+        match self.image {
+            Some(ImageFormat::Jpeg | ImageFormat::Avif) => true,
+            Some(ImageFormat::WebP) => !self.webp.lossless,
+            Some(ImageFormat::Png) => self.png.palette,
+            _ => false,
+        }
+    }
+
+    /// Whether the outputs can be encoded to a perceptual target, which needs them decoded to be scored.
+    /// AVIF can not be decoded here, it keeps the quality setting.
+    pub fn can_target(&self) -> bool{
+        self.is_lossy() && self.image != Some(ImageFormat::Avif)
+    }
+
+    /// Encodes `image` to `path` with the extension of the format appended, returning the written file along with how it was encoded.
+    /// With a max size `image` is replaced by its downscaled copy when it had to be.
+    pub fn apply(&self, image: &mut DynamicImage, path: PathBuf, cancel: &Cancel) -> Result<(PathBuf, Encoded), TransformationError>{
+        let Some(path) = self.get_output(&path) else {
+            return Err(TransformationError::new(Stage::Format, ErrorKind::NoFormat).with_output(&path));
+        };
+        let encoded = self.encode(image, cancel).map_err(|err| err.with_output(&path))?;
+        write_file(&path, &encoded.bytes)?;
+        Ok((path, encoded))
+    }
+
+    /// Encodes `image` in memory, the bytes `apply` would write.
+    /// The quality meeting the target caps the one searched to fit the max size.
+    pub fn encode(&self, image: &mut DynamicImage, cancel: &Cancel) -> Result<Encoded, TransformationError>{
+        let target = self.target.as_ref().filter(|_| self.can_target());
+        let mut quality = self.quality.value.min(MAX_QUALITY);
+        if let Some(target) = target {
+            let encoded = self.search_target(image, target, cancel)?;
+            if self.max_size.is_none() {
+                return Ok(encoded);
+            }
+            quality = encoded.quality.unwrap_or(quality);
+        }
+
+        let mut encoded = match &self.max_size {
+            Some(max_size) => self.encode_within(image, max_size, quality, cancel)?,
+            None => self.encode_at(image, quality, cancel)?,
+        };
+        if target.is_some() {
+            encoded.score = Some(self.score(image, &encoded.bytes)?);
+        }
+        Ok(encoded)
+    }
+
+    fn encode_at(&self, image: &DynamicImage, quality: u8, cancel: &Cancel) -> Result<Encoded, TransformationError>{
+        // Checked before every pass, searching a target or a max size stops at the next one.
+        cancel.check(Stage::Format)?;
+        let (bytes, colors) = match self.image {
+            Some(ImageFormat::Png) => self.png.encode(image, quality)?,
+            Some(ImageFormat::Tiff) => (encode_with_format(image, ImageFormat::Tiff)?, None),
+            Some(ImageFormat::Jpeg) => (self.jpeg.encode(image, quality)?, None),
+            Some(ImageFormat::WebP) => (self.webp.encode(image, quality)?, None),
+            Some(ImageFormat::Avif) => (self.avif.encode(image, quality, &self.speed)?, None),
+            _ => return Err(TransformationError::new(Stage::Format, ErrorKind::NoFormat)),
+        };
+        // libwebp picks its own quality to reach a target size,
+        // and a palette too far from the picture falls back to a lossless PNG whose quality means nothing.
+        let is_sized = self.image == Some(ImageFormat::WebP) && self.webp.target_size > 0;
+        let is_lossy = self.is_lossy() && !is_sized && (colors.is_some() || !self.is_palette());
+        Ok(Encoded { bytes, quality: is_lossy.then_some(quality), score: None, colors })
+    }
+
+    /// SSIM of the encoded bytes against the image they were encoded from.
+    fn score(&self, image: &DynamicImage, bytes: &[u8]) -> Result<f64, TransformationError>{
+        let format = self.image.unwrap_or(ImageFormat::Png);
+        if format == ImageFormat::Avif {
+            let unsupported = ErrorKind::Unsupported("AVIF outputs can not be decoded to be scored, pick a quality instead of a target");
+            return Err(TransformationError::new(Stage::Format, unsupported));
+        }
+        let decoded = image::load_from_memory_with_format(bytes, format)
+            .map_err(|err| TransformationError::new(Stage::Format, err))?;
+        Ok(ssim(image, &decoded))
+    }
+
+    /// Binary search of the lowest quality scoring at least the target, failing with the best score when even the highest quality does not.
+    fn search_target(&self, image: &DynamicImage, target: &Target, cancel: &Cancel) -> Result<Encoded, TransformationError>{
+        let (mut low, mut high) = (1u16, u16::from(MAX_QUALITY));
+        let mut best = None;
+        let mut highest = None;
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let mut encoded = self.encode_at(image, quality as u8, cancel)?;
+            let score = self.score(image, &encoded.bytes)?;
+            encoded.score = Some(score);
+            if score >= target.ssim {
+                best = Some(encoded);
+                if quality == low {
+                    break;
+                }
+                high = quality - 1;
+            } else {
+                highest = Some(encoded);
+                low = quality + 1;
+            }
+        }
+        match best {
+            Some(encoded) => Ok(encoded),
+            None => {
+                let score = highest.and_then(|encoded: Encoded| encoded.score).unwrap_or_default();
+                Err(TransformationError::new(Stage::Format, ErrorKind::Unreachable { score, target: target.ssim }))
+            }
+        }
+    }
+
+    /// Highest quality up to `quality` fitting in the max size, downscaling the image while even the lowest quality is too big.
+    fn encode_within(&self, image: &mut DynamicImage, max_size: &MaxSize, quality: u8, cancel: &Cancel) -> Result<Encoded, TransformationError>{
+        loop {
+            let encoded = if self.is_lossy() {
+                self.search_quality(image, max_size, quality, cancel)?
+            } else {
+                self.encode_at(image, quality, cancel)?
+            };
+            if encoded.bytes.len() as u64 <= max_size.bytes {
+                return Ok(encoded);
+            }
+
+            let width = (image.width() as f32 * DOWNSCALE) as u32;
+            let height = (image.height() as f32 * DOWNSCALE) as u32;
+            if !max_size.downscale || width < MIN_SIDE || height < MIN_SIDE {
+                let size = ErrorKind::TooLarge { bytes: encoded.bytes.len() as u64, max: max_size.bytes };
+                return Err(TransformationError::new(Stage::Format, size));
+            }
+            *image = image.resize(width, height, ResizeMethod::Lanczos3.to_image_filter());
+        }
+    }
+
+    /// Binary search of the highest quality fitting the max size, returning the lowest quality output when none does.
+    fn search_quality(&self, image: &DynamicImage, max_size: &MaxSize, quality: u8, cancel: &Cancel) -> Result<Encoded, TransformationError>{
+        // Bounds are wider than the qualities so stepping past 255 or below 0 cannot overflow.
+        let quality = quality.min(MAX_QUALITY);
+        let (mut low, mut high) = (u16::from(max_size.min_quality.min(quality)), u16::from(quality));
+        let mut best = None;
+        let mut lowest = None;
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let encoded = self.encode_at(image, quality as u8, cancel)?;
+            if encoded.bytes.len() as u64 <= max_size.bytes {
+                best = Some(encoded);
+                low = quality + 1;
+            } else {
+                lowest = Some(encoded);
+                if quality == low {
+                    break;
+                }
+                high = quality - 1;
+            }
+        }
+        match best.or(lowest) {
+            Some(encoded) => Ok(encoded),
+            None => self.encode_at(image, low.min(u16::from(MAX_QUALITY)) as u8, cancel),
+        }
+    }
+
+    /// File `apply` writes for `path`, the extension of the format appended.
+    pub fn get_output(&self, path: &Path) -> Option<PathBuf>{
+        self.get_extension().map(|extension| with_extension(path.to_path_buf(), extension))
+    }
+
+    pub fn get_extension(&self) -> Option<&'static str>{
+        match self.image {
+            Some(format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff | ImageFormat::WebP | ImageFormat::Avif)) => {
+                format.extensions_str().first().copied()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Formats are stored by the same keys the settings use, `png`, `jpg`, `webp`, `avif` or `tiff`.
+mod image_format {
+    use image::ImageFormat;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn to_key(image: Option<ImageFormat>) -> &'static str {
+        match image {
+            Some(ImageFormat::Png) => "png",
+            Some(ImageFormat::Jpeg) => "jpg",
+            Some(ImageFormat::WebP) => "webp",
+            Some(ImageFormat::Avif) => "avif",
+            Some(ImageFormat::Tiff) => "tiff",
+            _ => "none"
+        }
+    }
+
+    pub fn from_key(value: &str) -> Option<ImageFormat> {
+        match value {
             "png" => Some(ImageFormat::Png),
             "jpg" => Some(ImageFormat::Jpeg),
             "webp" => Some(ImageFormat::WebP),
@@ -38,61 +307,81 @@ impl Format{
             _ => None
         }
     }
-    
-    pub fn apply(&self, image: &mut DynamicImage, path: PathBuf) -> Result<(), TransformationError>{
-        match self.image {
-            Some(ImageFormat::Png) | Some(ImageFormat::Jpeg) | Some(ImageFormat::Tiff) => {
-                let format = self.image.unwrap();
-                save_image_with_format(image, path, format)
-            }
-            Some(ImageFormat::WebP) => save_image_as_webp(image, path, &self.quality),
-            Some(ImageFormat::Avif) => save_image_as_avif(image, path, &self.quality, &self.speed),
-            _ => {Ok(())},
-        }
+
+    pub fn serialize<S: Serializer>(image: &Option<ImageFormat>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(to_key(*image))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ImageFormat>, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Ok(from_key(&key))
     }
 }
 
-fn save_image_with_format(image: &mut DynamicImage, path: PathBuf, format: ImageFormat) -> Result<(), TransformationError>{
-    let extension = format.extensions_str().first().unwrap_or(&"png");
-    let path = path.with_extension(extension);
-    image
-        .save_with_format(path, format)
-        .map_err(|err| TransformationError::Format(err.to_string())
-        )
+/// File names can contain dots, the extension is appended instead of replacing what follows the last one.
+fn with_extension(path: PathBuf, extension: &str) -> PathBuf {
+    let mut path = path.into_os_string();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Writes through a `.part` file renamed once complete, so a stopped encode leaves nothing behind.
+pub(crate) fn write_file(path: &Path, bytes: &[u8]) -> Result<(), TransformationError> {
+    let part = with_extension(path.to_path_buf(), "part");
+    if let Err(err) = std::fs::write(&part, bytes).and_then(|_| std::fs::rename(&part, path)) {
+        let _ = std::fs::remove_file(&part);
+        return Err(TransformationError::new(Stage::Write, err).with_output(path));
+    }
+    Ok(())
 }
 
+pub(crate) fn encode_with_format(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, TransformationError>{
+    let mut bytes = Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, format)
+        .map_err(|err| TransformationError::new(Stage::Format, err))?;
+    Ok(bytes.into_inner())
+}
 
-fn save_image_as_webp(image: &mut DynamicImage, path: PathBuf, quality: &Quality) -> Result<(), TransformationError>{
-    let path = path.with_extension("webp");
-    let webp = webp::Encoder::from_image(&image)
-        .map_err(|err| TransformationError::Format(err.to_string()))?
-        .encode(quality.value as f32);
+/// Reads sizes such as `200 KB`, `1.5MB` or `250000`, the units being powers of 1024.
+pub(crate) fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_uppercase();
+    let number_end = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(number_end);
+    let number = number.parse::<f64>().ok()?;
+    let unit = match unit.trim() {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1024.0,
+        "M" | "MB" => 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * unit) as u64)
+}
 
-    std::fs::write(path, &*webp)
-        .map_err(|err| TransformationError::Format(err.to_string()))
+/// Writes sizes back the way `parse_size` reads them, in KB when they are a whole number of them.
+pub(crate) fn display_size(bytes: u64) -> String {
+    if bytes.is_multiple_of(1024) {
+        format!("{} KB", bytes / 1024)
+    } else {
+        bytes.to_string()
+    }
 }
 
-fn save_image_as_avif(image: &mut DynamicImage, path: PathBuf, quality: &Quality, speed: &Speed) -> Result<(), TransformationError>{
-    let path = path.with_extension("avif");
-    let raw = image.to_rgba8().into_raw();
-    let rgb: &[RGBA8] = raw.as_rgba();
-    let avif = Encoder::new()
-        .with_speed(speed.value)
-        .with_quality(quality.value as f32)
-        .encode_rgba(Img::new(
-            rgb,
-            image.width().try_into().unwrap(),
-            image.height().try_into().unwrap(),
-        ))
-        .map_err(|err| TransformationError::Format(err.to_string()))?;
+impl Default for Target {
+    fn default() -> Self {
+        Self { ssim: 0.98 }
+    }
+}
 
-    std::fs::write(
-        path,
-        avif.avif_file
-    ).map_err(|err| TransformationError::Format(err.to_string()))
+impl Default for MaxSize {
+    fn default() -> Self {
+        Self { bytes: 200 * 1024, min_quality: 30, downscale: true }
+    }
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Quality{
     pub value: u8
 }
@@ -116,7 +405,8 @@ impl From<String> for Quality{
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Speed {
     pub value: u8
 }
@@ -139,3 +429,147 @@ impl From<String> for Speed {
         Self { value: effort }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    /// Noise compresses badly, so the size drops steadily with the quality.
+    fn noise(width: u32, height: u32) -> DynamicImage {
+        let mut seed = 1u32;
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let [r, g, b, _] = seed.to_be_bytes();
+            image::Rgb([r, g, b])
+        }))
+    }
+
+    fn jpeg(max_size: MaxSize, quality: u8) -> Format {
+        Format { image: Some(ImageFormat::Jpeg), quality: Quality { value: quality }, max_size: Some(max_size), ..Default::default() }
+    }
+
+    fn size_at(format: &Format, image: &DynamicImage, quality: u8) -> u64 {
+        format.encode_at(image, quality, &Cancel::default()).unwrap().bytes.len() as u64
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("12 b"), Some(12));
+        assert_eq!(parse_size("200KB"), Some(200 * 1024));
+        assert_eq!(parse_size(" 200 k "), Some(200 * 1024));
+        assert_eq!(parse_size("1.5MB"), Some(1536 * 1024));
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("KB"), None);
+        assert_eq!(parse_size("2GB"), None);
+        assert_eq!(parse_size("-1KB"), None);
+        assert_eq!(parse_size("1.2.3"), None);
+    }
+
+    #[test]
+    fn displays_sizes_the_way_they_are_parsed() {
+        assert_eq!(display_size(200 * 1024), "200 KB");
+        assert_eq!(display_size(1000), "1000");
+        for bytes in [1000, 200 * 1024, 1536 * 1024] {
+            assert_eq!(parse_size(&display_size(bytes)), Some(bytes));
+        }
+    }
+
+    #[test]
+    fn turns_the_max_size_off_on_zero_or_garbage() {
+        let mut format = Format::default();
+        format.set_max_size("150 KB".to_string());
+        assert_eq!(format.max_size.as_ref().map(|max_size| max_size.bytes), Some(150 * 1024));
+        format.set_max_size("0".to_string());
+        assert_eq!(format.max_size, None);
+        format.set_max_size("lots".to_string());
+        assert_eq!(format.max_size, None);
+    }
+
+    #[test]
+    fn keeps_the_quality_when_it_fits() {
+        let mut image = noise(64, 64);
+        let format = jpeg(MaxSize { bytes: u64::MAX, ..Default::default() }, 80);
+        let encoded = format.encode(&mut image, &Cancel::default()).unwrap();
+        assert_eq!(encoded.quality, Some(80));
+        assert_eq!(image.width(), 64);
+    }
+
+    #[test]
+    fn fits_at_the_min_quality() {
+        let mut image = noise(64, 64);
+        let probe = jpeg(MaxSize::default(), 80);
+        let bytes = size_at(&probe, &image, 30);
+        assert!(bytes < size_at(&probe, &image, 31));
+
+        let format = jpeg(MaxSize { bytes, min_quality: 30, downscale: false }, 80);
+        let encoded = format.encode(&mut image, &Cancel::default()).unwrap();
+        assert_eq!(encoded.quality, Some(30));
+        assert!(encoded.bytes.len() as u64 <= bytes);
+    }
+
+    #[test]
+    fn too_large_without_downscale() {
+        let mut image = noise(64, 64);
+        let format = jpeg(MaxSize { bytes: 100, min_quality: 30, downscale: false }, 80);
+        let err = format.encode(&mut image, &Cancel::default()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TooLarge { max: 100, .. }));
+        assert_eq!(image.width(), 64);
+    }
+
+    #[test]
+    fn downscales_until_it_fits() {
+        let mut image = noise(128, 128);
+        let probe = jpeg(MaxSize::default(), 80);
+        let bytes = size_at(&probe, &noise(64, 64), 30);
+
+        let format = jpeg(MaxSize { bytes, min_quality: 30, downscale: true }, 80);
+        let encoded = format.encode(&mut image, &Cancel::default()).unwrap();
+        assert!(encoded.bytes.len() as u64 <= bytes);
+        assert!(image.width() < 128 && image.width() >= MIN_SIDE);
+        assert_eq!(image.width(), image.height());
+    }
+
+    #[test]
+    fn clamps_the_quality() {
+        let mut image = noise(32, 32);
+        let format = jpeg(MaxSize { bytes: u64::MAX, ..Default::default() }, u8::MAX);
+        let encoded = format.encode(&mut image, &Cancel::default()).unwrap();
+        assert_eq!(encoded.quality, Some(MAX_QUALITY));
+
+        let encoded = format.search_quality(&image, &MaxSize { bytes: u64::MAX, ..Default::default() }, u8::MAX, &Cancel::default()).unwrap();
+        assert_eq!(encoded.quality, Some(MAX_QUALITY));
+    }
+
+    #[test]
+    fn reaches_the_target() {
+        let mut image = noise(64, 64);
+        let format = Format { image: Some(ImageFormat::Jpeg), target: Some(Target { ssim: 0.9 }), ..Default::default() };
+        let encoded = format.encode(&mut image, &Cancel::default()).unwrap();
+        assert!(encoded.score.unwrap() >= 0.9);
+        let quality = encoded.quality.unwrap();
+        assert!(quality > 1 && quality < MAX_QUALITY);
+    }
+
+    #[test]
+    fn fails_an_unreachable_target() {
+        let mut image = noise(64, 64);
+        let format = Format { image: Some(ImageFormat::Jpeg), target: Some(Target { ssim: 1.0 }), ..Default::default() };
+        let err = format.encode(&mut image, &Cancel::default()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Unreachable { score, target } if score < 1.0 && target == 1.0));
+    }
+
+    #[test]
+    fn scores_transparent_webp_by_what_is_seen() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(64, 64, |x, y| {
+            let alpha = if (x / 16 + y / 16) % 2 == 0 { 255 } else { 0 };
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, 128, alpha])
+        }));
+        let format = Format { image: Some(ImageFormat::WebP), ..Default::default() };
+        let encoded = format.encode_at(&image, MAX_QUALITY, &Cancel::default()).unwrap();
+        let score = format.score(&image, &encoded.bytes).unwrap();
+        assert!(score > 0.95, "{}", score);
+    }
+}
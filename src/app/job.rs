@@ -0,0 +1,56 @@
+use crate::app::application::Application;
+use crate::app::format::Format;
+use crate::app::picture::Picture;
+use crate::app::progress::Cancel;
+use crate::app::resize::Resize;
+use crate::app::rotate::Rotate;
+use crate::app::variants::{Resized, Variants};
+use crate::error::{Stage, TransformationError};
+use std::path::PathBuf;
+
+/// A picture along with the settings it is rendered with, all a background encode needs from the application.
+/// The preview also compares it to know when the focused picture has to be encoded again.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Job {
+    pub path: PathBuf,
+    /// EXIF orientation of the picture.
+    pub rotation: Option<u32>,
+    pub resize: Resize,
+    pub format: Format,
+    pub rotate: Rotate,
+    pub variants: Variants,
+}
+
+impl Job {
+    /// The settings of `app` with the overrides of `picture` applied.
+    pub fn new(app: &Application, picture: &Picture) -> Self {
+        Self {
+            path: picture.path.clone(),
+            rotation: picture.metadata.rotation,
+            resize: picture.overrides.get_resize(&app.resize).clone(),
+            format: picture.overrides.get_format(&app.format).clone(),
+            rotate: picture.overrides.get_rotate(&app.rotate).clone(),
+            variants: app.variants.clone(),
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    /// Decodes, orients and resizes the picture, along with the formats each resized image is encoded to.
+    /// Cancelling is checked before each of the steps.
+    pub(crate) fn render(&self, cancel: &Cancel) -> Result<(Resized, Vec<Format>), TransformationError> {
+        let with_input = |e: TransformationError| e.with_input(&self.path);
+        cancel.check(Stage::Load).map_err(with_input)?;
+        let mut image = image::open(&self.path).map_err(|e| TransformationError::new(Stage::Load, e).with_input(&self.path))?;
+
+        // Orient first so the sizes asked for are the ones of the picture as it is seen.
+        cancel.check(Stage::Rotate).map_err(with_input)?;
+        self.rotate.apply(&mut image, self.rotation).map_err(with_input)?;
+
+        cancel.check(Stage::Resize).map_err(with_input)?;
+        let resized = self.variants.resize(&image, &self.resize).map_err(with_input)?;
+        Ok((resized, self.variants.formats(&self.format, &self.path)))
+    }
+}
@@ -0,0 +1,298 @@
+use image::{DynamicImage, RgbImage};
+use mozjpeg_sys as ffi;
+use serde::{Deserialize, Serialize};
+use crate::error::{EncoderError, ErrorKind, Stage, TransformationError};
+use std::mem;
+use std::ptr;
+use std::sync::Arc;
+#[cfg(feature = "gui")]
+use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
+
+/// Bytes libjpeg writes before they are moved to the output.
+const DESTINATION_BUFFER: usize = 64 * 1024;
+
+/// Settings of the JPEG encoder, the defaults being the ones of mozjpeg: the smallest files for a slower encoding.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Jpeg {
+    /// Progressive scans, the picture showing blurry first and sharpening as it loads.
+    pub progressive: bool,
+    pub subsampling: Subsampling,
+    /// Huffman tables computed for the picture instead of the standard ones.
+    pub optimize_coding: bool,
+    /// Trellis quantization, rounding each block to what costs the fewest bits for the same quality.
+    pub trellis: bool,
+}
+
+/// Resolution of the colours against the one of the brightness.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Subsampling {
+    #[serde(rename = "444")]
+    Full,
+    #[serde(rename = "422")]
+    Half,
+    #[default]
+    #[serde(rename = "420")]
+    Quarter,
+}
+
+impl Default for Jpeg {
+    fn default() -> Self {
+        Self { progressive: true, subsampling: Subsampling::default(), optimize_coding: true, trellis: true }
+    }
+}
+
+impl Jpeg {
+    /// Quickest settings, for files that are only shown by the application.
+    pub fn fastest() -> Self {
+        Self { progressive: false, subsampling: Subsampling::Quarter, optimize_coding: false, trellis: false }
+    }
+
+    pub fn set_subsampling(&mut self, value: String) {
+        self.subsampling = match value.as_str() {
+            "444" => Subsampling::Full,
+            "422" => Subsampling::Half,
+            _ => Subsampling::Quarter,
+        }
+    }
+
+    pub fn encode(&self, image: &DynamicImage, quality: u8) -> Result<Vec<u8>, TransformationError> {
+        let rgb = image.to_rgb8();
+        // mozjpeg reports its errors by unwinding.
+        let result = std::panic::catch_unwind(|| self.compress(&rgb, quality));
+        result.map_err(|panic| {
+            let message = panic
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "mozjpeg could not encode the picture".to_string());
+            TransformationError::new(Stage::Format, ErrorKind::Jpeg(Arc::new(EncoderError(message))))
+        })
+    }
+
+    /// Compresses with the default profile of mozjpeg, the one for the smallest files.
+    /// The mozjpeg crate only turns trellis quantization off along with the whole profile, so the library is called directly.
+    fn compress(&self, rgb: &RgbImage, quality: u8) -> Vec<u8> {
+        // Declared before the compressor, which points to them and is dropped first.
+        let mut err = unsafe { mem::zeroed::<ffi::jpeg_error_mgr>() };
+        let mut destination = Destination::new();
+        let mut compressor = Compressor(unsafe { mem::zeroed() });
+        let cinfo = &mut compressor.0;
+        let (width, height) = rgb.dimensions();
+        // An empty picture has no rows, libjpeg reports it once started.
+        let rows: Vec<*const u8> = rgb.as_raw().chunks_exact((width as usize * 3).max(1)).map(|row| row.as_ptr()).collect();
+
+        unsafe {
+            ffi::jpeg_std_error(&mut err);
+            err.error_exit = Some(unwind_error_exit);
+            err.emit_message = Some(silence_message);
+            cinfo.common.err = &mut err;
+            ffi::jpeg_CreateCompress(cinfo, ffi::JPEG_LIB_VERSION, mem::size_of::<ffi::jpeg_compress_struct>());
+            cinfo.dest = ptr::addr_of_mut!(destination).cast();
+
+            cinfo.image_width = width;
+            cinfo.image_height = height;
+            cinfo.input_components = 3;
+            cinfo.in_color_space = ffi::JCS_RGB;
+            ffi::jpeg_set_defaults(cinfo);
+            if !self.trellis {
+                ffi::jpeg_c_set_bool_param(cinfo, ffi::JBOOLEAN_TRELLIS_QUANT, 0);
+                ffi::jpeg_c_set_bool_param(cinfo, ffi::JBOOLEAN_TRELLIS_QUANT_DC, 0);
+            }
+            ffi::jpeg_set_quality(cinfo, quality.clamp(1, 100) as i32, 0);
+            if self.progressive {
+                ffi::jpeg_simple_progression(cinfo);
+            } else {
+                ffi::jpeg_c_set_bool_param(cinfo, ffi::JBOOLEAN_OPTIMIZE_SCANS, 0);
+                cinfo.scan_info = ptr::null();
+            }
+            cinfo.optimize_coding = self.optimize_coding as i32;
+            // Brightness at full resolution, the colours at the one of the subsampling.
+            let (horizontal, vertical) = self.subsampling.pixel_size();
+            let components = std::slice::from_raw_parts_mut(cinfo.comp_info, cinfo.num_components as usize);
+            for (index, component) in components.iter_mut().enumerate() {
+                component.h_samp_factor = if index == 0 { horizontal as i32 } else { 1 };
+                component.v_samp_factor = if index == 0 { vertical as i32 } else { 1 };
+            }
+
+            ffi::jpeg_start_compress(cinfo, 1);
+            while cinfo.next_scanline < height {
+                let next = cinfo.next_scanline;
+                ffi::jpeg_write_scanlines(cinfo, rows.as_ptr().add(next as usize), height - next);
+            }
+            ffi::jpeg_finish_compress(cinfo);
+            cinfo.dest = ptr::null_mut();
+        }
+        destination.bytes
+    }
+}
+
+/// Destroys the compressor however the compression ends, unwinding included.
+struct Compressor(ffi::jpeg_compress_struct);
+
+impl Drop for Compressor {
+    fn drop(&mut self) {
+        unsafe { ffi::jpeg_destroy_compress(&mut self.0) }
+    }
+}
+
+/// Where libjpeg writes the file, its manager first so the pointer libjpeg is given leads back to the buffers.
+#[repr(C)]
+struct Destination {
+    manager: ffi::jpeg_destination_mgr,
+    buffer: Vec<u8>,
+    bytes: Vec<u8>,
+}
+
+impl Destination {
+    fn new() -> Self {
+        let manager = ffi::jpeg_destination_mgr {
+            next_output_byte: ptr::null_mut(),
+            free_in_buffer: DESTINATION_BUFFER,
+            init_destination: Some(flush_destination),
+            empty_output_buffer: Some(empty_destination),
+            term_destination: Some(flush_destination),
+        };
+        Self { manager, buffer: vec![0; DESTINATION_BUFFER], bytes: Vec::new() }
+    }
+
+    /// Keeps what libjpeg wrote to the buffer and hands the whole of it back.
+    fn flush(&mut self) {
+        let written = self.buffer.len() - self.manager.free_in_buffer;
+        self.bytes.extend_from_slice(&self.buffer[..written]);
+        self.manager.next_output_byte = self.buffer.as_mut_ptr();
+        self.manager.free_in_buffer = self.buffer.len();
+    }
+}
+
+unsafe extern "C-unwind" fn flush_destination(cinfo: &mut ffi::jpeg_compress_struct) {
+    (*cinfo.dest.cast::<Destination>()).flush();
+}
+
+unsafe extern "C-unwind" fn empty_destination(cinfo: &mut ffi::jpeg_compress_struct) -> ffi::boolean {
+    flush_destination(cinfo);
+    1
+}
+
+unsafe extern "C-unwind" fn unwind_error_exit(cinfo: &mut ffi::jpeg_common_struct) {
+    let mut buffer = [0_u8; 80];
+    if let Some(format_message) = cinfo.err.as_ref().and_then(|err| err.format_message) {
+        // The binding declares the buffer the message is written to as shared.
+        let format_message = mem::transmute::<
+            unsafe extern "C-unwind" fn(&mut ffi::jpeg_common_struct, &[u8; 80]),
+            unsafe extern "C-unwind" fn(&mut ffi::jpeg_common_struct, &mut [u8; 80]),
+        >(format_message);
+        format_message(cinfo, &mut buffer);
+    }
+    let message = buffer.split(|byte| *byte == 0).next().unwrap_or_default();
+    std::panic::resume_unwind(Box::new(format!("mozjpeg : {}", String::from_utf8_lossy(message))));
+}
+
+unsafe extern "C-unwind" fn silence_message(_cinfo: &mut ffi::jpeg_common_struct, _level: std::os::raw::c_int) {}
+
+impl Subsampling {
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            Subsampling::Full => "444",
+            Subsampling::Half => "422",
+            Subsampling::Quarter => "420",
+        }
+    }
+
+    /// Pixels covered by a colour sample, horizontally and vertically.
+    fn pixel_size(&self) -> (u8, u8) {
+        match self {
+            Subsampling::Full => (1, 1),
+            Subsampling::Half => (2, 1),
+            Subsampling::Quarter => (2, 2),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+const OPTIONS: [(&str, &str); 3] = [("444", "4:4:4 (no subsampling)"), ("422", "4:2:2"), ("420", "4:2:0")];
+#[cfg(feature = "gui")]
+impl ToHtml for Subsampling {
+    fn to_html(&self) -> Element {
+        let selected = self.get_key();
+        rsx!{
+            for (value , label) in OPTIONS {
+                option { value, selected: value == selected, {label} }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, ImageFormat, Rgb};
+
+    fn picture(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| Rgb([(x * 7) as u8, (y * 5) as u8, ((x ^ y) * 3) as u8])))
+    }
+
+    /// Start of frame marker, `0xC0` for baseline and `0xC2` for progressive, along with the sampling factors of each component.
+    fn frame(bytes: &[u8]) -> (u8, Vec<u8>) {
+        let mut index = 2;
+        loop {
+            let (marker, length) = (bytes[index + 1], u16::from_be_bytes([bytes[index + 2], bytes[index + 3]]) as usize);
+            if matches!(marker, 0xC0..=0xC2) {
+                let components = bytes[index + 9] as usize;
+                let factors = (0..components).map(|component| bytes[index + 11 + component * 3]).collect();
+                return (marker, factors);
+            }
+            index += 2 + length;
+        }
+    }
+
+    fn round_trip(jpeg: &Jpeg, image: &DynamicImage) -> Vec<u8> {
+        let bytes = jpeg.encode(image, 80).unwrap();
+        let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::Jpeg).unwrap();
+        assert_eq!(decoded.dimensions(), image.dimensions());
+        bytes
+    }
+
+    #[test]
+    fn encodes_progressive_and_baseline() {
+        let image = picture(64, 48);
+        let progressive = round_trip(&Jpeg::default(), &image);
+        assert_eq!(frame(&progressive).0, 0xC2);
+        let baseline = round_trip(&Jpeg { progressive: false, ..Jpeg::default() }, &image);
+        assert!(matches!(frame(&baseline).0, 0xC0 | 0xC1));
+    }
+
+    #[test]
+    fn encodes_each_subsampling() {
+        let image = picture(64, 48);
+        for (subsampling, luma) in [(Subsampling::Full, 0x11), (Subsampling::Half, 0x21), (Subsampling::Quarter, 0x22)] {
+            let bytes = round_trip(&Jpeg { subsampling, ..Jpeg::default() }, &image);
+            assert_eq!(frame(&bytes).1, [luma, 0x11, 0x11]);
+        }
+    }
+
+    #[test]
+    fn encodes_with_and_without_trellis() {
+        let image = picture(64, 48);
+        let trellis = round_trip(&Jpeg::default(), &image);
+        let plain = round_trip(&Jpeg { trellis: false, ..Jpeg::default() }, &image);
+        assert_ne!(trellis, plain);
+        round_trip(&Jpeg::fastest(), &image);
+    }
+
+    #[test]
+    fn encodes_a_single_pixel() {
+        round_trip(&Jpeg::default(), &picture(1, 1));
+        round_trip(&Jpeg::fastest(), &picture(1, 1));
+    }
+
+    #[test]
+    fn fails_on_an_empty_picture() {
+        let err = Jpeg::default().encode(&picture(0, 0), 80).unwrap_err();
+        assert_eq!(err.stage, Stage::Format);
+        assert!(matches!(err.kind, ErrorKind::Jpeg(_)));
+        // Nothing is left behind by the unwinding, the next picture still encodes.
+        round_trip(&Jpeg::default(), &picture(8, 8));
+    }
+}
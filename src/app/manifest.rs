@@ -0,0 +1,283 @@
+use crate::app::format::Encoded;
+use crate::app::picture::Picture;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Every file written for the transformed pictures, saved as `manifest.json` in the destination.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub pictures: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub source: String,
+    pub width: usize,
+    pub height: usize,
+    pub weight: u64,
+    pub outputs: Vec<Output>,
+    pub html: String,
+}
+
+/// A file written by the transformation, its path relative to the destination.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Output {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub mime: String,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+    /// Quality the lossy formats were encoded at, the one searched when fitting a max size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    /// SSIM of the output against the picture, measured when encoding to a perceptual target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Colours of the palette, for the PNG outputs reduced to one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<u16>,
+    #[serde(skip)]
+    pub file: PathBuf,
+}
+
+impl Output {
+    pub fn new(file: PathBuf, image: &DynamicImage, destination: &Path, encoded: &Encoded) -> Self {
+        let path = file
+            .strip_prefix(destination)
+            .unwrap_or(&file)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mime = ImageFormat::from_path(&file)
+            .map(|format| format.to_mime_type().to_string())
+            .unwrap_or_default();
+        Self {
+            path,
+            mime,
+            width: image.width(),
+            height: image.height(),
+            bytes: encoded.bytes.len() as u64,
+            quality: encoded.quality,
+            score: encoded.score,
+            colors: encoded.colors,
+            file,
+        }
+    }
+
+    /// How the output was encoded, such as `quality 62 · SSIM 0.981`, empty for the lossless formats.
+    pub fn get_encoding(&self) -> String {
+        get_encoding(self.quality, self.score, self.colors)
+    }
+}
+
+pub(crate) fn get_encoding(quality: Option<u8>, score: Option<f64>, colors: Option<u16>) -> String {
+    let mut parts = Vec::new();
+    if let Some(quality) = quality {
+        parts.push(format!("quality {}", quality));
+    }
+    if let Some(colors) = colors {
+        parts.push(format!("{} colours", colors));
+    }
+    if let Some(score) = score {
+        parts.push(format!("SSIM {:.3}", score));
+    }
+    parts.join(" · ")
+}
+
+impl ManifestEntry {
+    pub fn new(picture: &Picture, outputs: Vec<Output>) -> Self {
+        let source = picture.name.directory.join(picture.get_name());
+        let source = source
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let html = to_html(&picture.name.source_name, &outputs);
+        Self {
+            source,
+            width: picture.metadata.width,
+            height: picture.metadata.height,
+            weight: picture.metadata.weight,
+            outputs,
+            html,
+        }
+    }
+}
+
+impl Manifest {
+    /// The `manifest.json` of an earlier run in `destination`, empty when there is none or it can not be read.
+    pub fn read(destination: &Path) -> Self {
+        std::fs::read_to_string(destination.join("manifest.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Adds the pictures of `other`, replacing the entries of the same sources so a partial run keeps the others listed.
+    pub fn merge(&mut self, other: Manifest) {
+        for entry in other.pictures {
+            match self.pictures.iter_mut().find(|existing| existing.source == entry.source) {
+                Some(existing) => *existing = entry,
+                None => self.pictures.push(entry),
+            }
+        }
+    }
+
+    /// Writes `manifest.json` and `snippets.html`, the markup of every picture under a comment naming its source, in `destination`.
+    pub fn write(&self, destination: &Path) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(destination.join("manifest.json"), json)?;
+
+        let snippets = self.pictures
+            .iter()
+            // Escaped so a name holding `-->` can not close the comment early.
+            .map(|entry| format!("<!-- {} -->\n{}", escape(&entry.source), entry.html))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        std::fs::write(destination.join("snippets.html"), snippets + "\n")
+    }
+}
+
+/// `<picture>` markup with a `<source>` per format, falling back on a JPEG or PNG `<img>` when there is one.
+fn to_html(alt: &str, outputs: &[Output]) -> String {
+    let mut formats: Vec<&str> = Vec::new();
+    for output in outputs {
+        if !formats.contains(&output.mime.as_str()) {
+            formats.push(&output.mime);
+        }
+    }
+    let fallback = formats
+        .iter()
+        .copied()
+        .find(|mime| *mime == "image/jpeg" || *mime == "image/png")
+        .or(formats.last().copied())
+        .unwrap_or_default();
+
+    let Some(largest) = outputs
+        .iter()
+        .filter(|output| output.mime == fallback)
+        .max_by_key(|output| output.width)
+    else {
+        return String::new();
+    };
+
+    let is_responsive = outputs.iter().filter(|output| output.mime == fallback).count() > 1;
+    let srcset = |mime: &str| {
+        outputs
+            .iter()
+            .filter(|output| output.mime == mime)
+            .map(|output| if is_responsive { format!("{} {}w", encode_url(&output.path), output.width) } else { encode_url(&output.path) })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let sizes = if is_responsive { " sizes=\"100vw\"" } else { "" };
+
+    let mut html = String::from("<picture>\n");
+    for mime in formats.iter().filter(|mime| **mime != fallback) {
+        html += &format!("  <source type=\"{}\" srcset=\"{}\"{}>\n", mime, escape(&srcset(mime)), sizes);
+    }
+    let img_srcset = if is_responsive { format!(" srcset=\"{}\"{}", escape(&srcset(fallback)), sizes) } else { String::new() };
+    html += &format!(
+        "  <img src=\"{}\"{} width=\"{}\" height=\"{}\" alt=\"{}\" loading=\"lazy\" decoding=\"async\">\n",
+        escape(&encode_url(&largest.path)), img_srcset, largest.width, largest.height, escape(alt)
+    );
+    html += "</picture>";
+    html
+}
+
+/// Percent-encodes a relative path for the markup, a space or a comma would otherwise break a `srcset`.
+fn encode_url(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(path: &str, mime: &str, width: u32) -> Output {
+        Output {
+            path: path.to_string(),
+            mime: mime.to_string(),
+            width,
+            height: width / 2,
+            bytes: 1000,
+            quality: None,
+            score: None,
+            colors: None,
+            file: PathBuf::new(),
+        }
+    }
+
+    fn entry(source: &str, weight: u64) -> ManifestEntry {
+        let outputs = vec![output("photo.jpg", "image/jpeg", 400)];
+        ManifestEntry { source: source.to_string(), width: 400, height: 200, weight, html: to_html(source, &outputs), outputs }
+    }
+
+    #[test]
+    fn encodes_urls() {
+        assert_eq!(encode_url("dir/photo-1_a.jpg"), "dir/photo-1_a.jpg");
+        assert_eq!(encode_url("my photo,2.jpg"), "my%20photo%2C2.jpg");
+        assert_eq!(encode_url("café.png"), "caf%C3%A9.png");
+        assert_eq!(encode_url("a\"b<c>.png"), "a%22b%3Cc%3E.png");
+    }
+
+    #[test]
+    fn escapes_attributes() {
+        assert_eq!(escape("a & \"b\" <c>"), "a &amp; &quot;b&quot; &lt;c&gt;");
+    }
+
+    #[test]
+    fn lists_every_format() {
+        let outputs = vec![
+            output("photo-400.webp", "image/webp", 400),
+            output("photo-800.webp", "image/webp", 800),
+            output("photo-400.jpg", "image/jpeg", 400),
+            output("photo-800.jpg", "image/jpeg", 800),
+        ];
+        let html = to_html("A \"photo\"", &outputs);
+        assert!(html.contains("<source type=\"image/webp\" srcset=\"photo-400.webp 400w, photo-800.webp 800w\" sizes=\"100vw\">"));
+        assert!(html.contains("<img src=\"photo-800.jpg\" srcset=\"photo-400.jpg 400w, photo-800.jpg 800w\" sizes=\"100vw\" width=\"800\" height=\"400\""));
+        assert!(html.contains("alt=\"A &quot;photo&quot;\""));
+        assert_eq!(to_html("empty", &[]), "");
+    }
+
+    #[test]
+    fn merges_by_source() {
+        let mut manifest = Manifest { pictures: vec![entry("a.png", 1), entry("b.png", 1)] };
+        manifest.merge(Manifest { pictures: vec![entry("b.png", 2), entry("c.png", 2)] });
+        let weights = manifest.pictures.iter().map(|entry| (entry.source.as_str(), entry.weight)).collect::<Vec<_>>();
+        assert_eq!(weights, [("a.png", 1), ("b.png", 2), ("c.png", 2)]);
+    }
+
+    #[test]
+    fn writes_and_reads_back() {
+        let destination = std::env::temp_dir().join(format!("manifest-test-{}", std::process::id()));
+        std::fs::create_dir_all(&destination).unwrap();
+        assert_eq!(Manifest::read(&destination), Manifest::default());
+
+        let manifest = Manifest { pictures: vec![entry("evil --><script>.png", 1)] };
+        manifest.write(&destination).unwrap();
+        let snippets = std::fs::read_to_string(destination.join("snippets.html")).unwrap();
+        assert!(snippets.starts_with("<!-- evil --&gt;&lt;script&gt;.png -->\n<picture>"));
+        assert!(!snippets.contains("<script>"));
+        assert_eq!(Manifest::read(&destination), manifest);
+        std::fs::remove_dir_all(&destination).unwrap();
+    }
+}
@@ -4,12 +4,48 @@ mod resize;
 mod format;
 mod rotate;
 mod sort;
+mod scan;
+mod filters;
+mod watch;
+mod preset;
+mod variants;
+mod manifest;
 mod picture;
+mod progress;
+mod estimate;
+mod ssim;
+mod compare;
+mod preview;
+mod job;
+mod thumbnails;
+mod jpeg;
+mod png;
+mod palette;
+mod webp;
+mod avif;
 
 pub use application::Application;
-pub use paths::Paths;
+pub use paths::{Paths, cache_dir, config_dir};
 pub use resize::{Resize, ResizeType, ResizeMethod};
-pub use format::{Format, Quality, Speed};
+pub use format::{Encoded, Format, MaxSize, Quality, Speed, Target};
+#[cfg(feature = "gui")]
+pub(crate) use format::OPTIONS as FORMAT_OPTIONS;
 pub use rotate::{Rotate, Angle};
 pub use sort::{ SortType, SortOrder};
-pub use picture::Picture;
\ No newline at end of file
+pub use scan::{Scan, Symlinks};
+pub use filters::Filters;
+pub use watch::{Watch, WatchEntry, WatchEvent};
+pub use preset::{Preset, Presets};
+pub use variants::Variants;
+pub use manifest::{Manifest, ManifestEntry, Output};
+pub use picture::{Picture, Overrides, Status, format_weight};
+pub use progress::{Progress, BatchProgress, Cancel, Failure};
+pub use compare::{Comparison, Side};
+pub use preview::Preview;
+pub use job::Job;
+pub use thumbnails::Thumbnails;
+pub use jpeg::{Jpeg, Subsampling};
+pub use png::Png;
+pub use webp::Webp;
+pub use avif::{Avif, AlphaMode, ColorModel};
+pub use estimate::{Estimate, PictureEstimate};
\ No newline at end of file
@@ -0,0 +1,230 @@
+use color_quant::NeuQuant;
+use image::{DynamicImage, RgbaImage};
+use crate::app::ssim::ssim;
+use crate::error::{Stage, TransformationError};
+
+/// Sampling of NeuQuant, from 1 for the best palette to 30 for the fastest.
+const SAMPLING: i32 = 10;
+const MIN_COLORS: usize = 2;
+const MAX_COLORS: usize = 256;
+
+/// A picture reduced to a palette of 256 colours or less, alpha included.
+pub struct Quantized {
+    pub width: u32,
+    pub height: u32,
+    /// Palette entry of every pixel.
+    pub indices: Vec<u8>,
+    /// RGBA entries of the palette, only the ones used are kept.
+    pub palette: Vec<[u8; 4]>,
+}
+
+impl Quantized {
+    /// The palette with the fewest colours whose SSIM against `image`, in percent, reaches `quality`, along with its SSIM.
+    /// The colours are searched by halves, the SSIM growing with them. When even 256 colours fall short, those are kept.
+    pub fn fewest(image: &DynamicImage, quality: u8, dithering: bool) -> (Self, f64) {
+        let target = quality.min(100) as f64 / 100.0;
+        let mut best = Self::scored(image, MAX_COLORS, dithering);
+        if best.1 < target {
+            return best;
+        }
+        // `high` always reaches the target, `best` being its palette.
+        let (mut low, mut high) = (MIN_COLORS, MAX_COLORS);
+        while low < high {
+            let middle = (low + high) / 2;
+            let scored = Self::scored(image, middle, dithering);
+            if scored.1 >= target {
+                high = middle;
+                best = scored;
+            } else {
+                low = middle + 1;
+            }
+        }
+        best
+    }
+
+    fn scored(image: &DynamicImage, colors: usize, dithering: bool) -> (Self, f64) {
+        let quantized = Self::new(image, colors, dithering);
+        let score = ssim(image, &quantized.to_image());
+        (quantized, score)
+    }
+
+    /// Reduces `image` to a palette of `colors` entries at most, from 2 to 256.
+    /// With `dithering` the error of each pixel is spread to its neighbours, Floyd–Steinberg style.
+    pub fn new(image: &DynamicImage, colors: usize, dithering: bool) -> Self {
+        let rgba = image.to_rgba8();
+        let colors = colors.clamp(MIN_COLORS, MAX_COLORS);
+        let quant = NeuQuant::new(SAMPLING, colors, rgba.as_raw());
+        let indices = if dithering {
+            dither(&rgba, &quant)
+        } else {
+            rgba.pixels().map(|pixel| quant.index_of(&pixel.0) as u8).collect()
+        };
+
+        let palette: Vec<[u8; 4]> = quant
+            .color_map_rgba()
+            .chunks_exact(4)
+            .map(|color| [color[0], color[1], color[2], color[3]])
+            .collect();
+        let mut quantized = Self { width: rgba.width(), height: rgba.height(), indices, palette };
+        quantized.drop_unused();
+        quantized
+    }
+
+    pub fn colors(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// The picture as it looks once reduced, to be compared with the original.
+    pub fn to_image(&self) -> DynamicImage {
+        let pixels = self.indices.iter().flat_map(|index| self.palette[*index as usize]).collect();
+        let image = RgbaImage::from_raw(self.width, self.height, pixels).unwrap_or_default();
+        DynamicImage::ImageRgba8(image)
+    }
+
+    /// Encodes an indexed PNG, the alpha of the palette going to its `tRNS` chunk.
+    pub fn encode(&self) -> Result<Vec<u8>, TransformationError> {
+        let palette: Vec<u8> = self.palette.iter().flat_map(|color| [color[0], color[1], color[2]]).collect();
+        let alpha: Vec<u8> = self.palette.iter().map(|color| color[3]).collect();
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette);
+        if alpha.iter().any(|alpha| *alpha < u8::MAX) {
+            encoder.set_trns(alpha);
+        }
+        encoder
+            .write_header()
+            .and_then(|mut writer| {
+                writer.write_image_data(&self.indices)?;
+                writer.finish()
+            })
+            .map_err(|err| TransformationError::new(Stage::Format, err))?;
+        Ok(bytes)
+    }
+
+    /// Removes the palette entries no pixel ended up using, renumbering the others.
+    fn drop_unused(&mut self) {
+        let mut used = [false; MAX_COLORS];
+        for index in &self.indices {
+            used[*index as usize] = true;
+        }
+        let mut renumbered = [0_u8; MAX_COLORS];
+        let mut palette = Vec::new();
+        for (index, color) in self.palette.iter().enumerate() {
+            if used[index] {
+                renumbered[index] = palette.len() as u8;
+                palette.push(*color);
+            }
+        }
+        for index in self.indices.iter_mut() {
+            *index = renumbered[*index as usize];
+        }
+        self.palette = palette;
+    }
+}
+
+/// Maps every pixel to the palette, pushing what it lost to the pixels right and below it.
+fn dither(rgba: &RgbaImage, quant: &NeuQuant) -> Vec<u8> {
+    let width = rgba.width() as usize;
+    let mut current = vec![[0_f32; 4]; width + 2];
+    let mut next = vec![[0_f32; 4]; width + 2];
+    let mut indices = Vec::with_capacity(rgba.as_raw().len() / 4);
+
+    for row in rgba.rows() {
+        for (x, pixel) in row.enumerate() {
+            let mut wanted = [0_u8; 4];
+            for channel in 0..4 {
+                wanted[channel] = (pixel.0[channel] as f32 + current[x + 1][channel]).round().clamp(0.0, 255.0) as u8;
+            }
+            let index = quant.index_of(&wanted);
+            let color = quant.lookup(index).unwrap_or(wanted);
+            indices.push(index as u8);
+
+            for channel in 0..4 {
+                let error = wanted[channel] as f32 - color[channel] as f32;
+                current[x + 2][channel] += error * 7.0 / 16.0;
+                next[x][channel] += error * 3.0 / 16.0;
+                next[x + 1][channel] += error * 5.0 / 16.0;
+                next[x + 2][channel] += error / 16.0;
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|error| *error = [0.0; 4]);
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, GrayImage, ImageFormat, Luma, Rgba};
+
+    /// Four flat quarters, a palette of four colours renders them exactly.
+    fn quarters() -> DynamicImage {
+        const COLORS: [[u8; 4]; 4] = [[220, 40, 40, 255], [40, 200, 40, 255], [40, 40, 210, 255], [240, 240, 240, 0]];
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, y| Rgba(COLORS[(x / 16 + 2 * (y / 16)) as usize])))
+    }
+
+    fn noise(width: u32, height: u32) -> DynamicImage {
+        let mut seed = 7u32;
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let [r, g, b, _] = seed.to_be_bytes();
+            Rgba([r, g, b, 255])
+        }))
+    }
+
+    #[test]
+    fn keeps_the_fewest_colours_reaching_the_quality() {
+        let (quantized, score) = Quantized::fewest(&quarters(), 99, false);
+        assert!(score >= 0.99, "{}", score);
+        assert!(quantized.colors() <= 8, "{}", quantized.colors());
+        assert!(quantized.colors() >= 4, "{}", quantized.colors());
+
+        let (fewer, _) = Quantized::fewest(&quarters(), 0, false);
+        assert!(fewer.colors() <= MIN_COLORS);
+    }
+
+    #[test]
+    fn keeps_every_colour_when_the_quality_is_out_of_reach() {
+        let image = noise(64, 64);
+        let (quantized, score) = Quantized::fewest(&image, 100, false);
+        assert!(score < 1.0);
+        assert!(quantized.colors() > 128, "{}", quantized.colors());
+    }
+
+    #[test]
+    fn dithering_keeps_the_mean_brightness() {
+        // Mid grey with two colours only, black and white, averages out when dithered.
+        let image = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, _| Luma([if x == 0 { 0 } else if x == 1 { 255 } else { 128 }])));
+        let mean = |quantized: &Quantized| {
+            let pixels = quantized.to_image().to_luma8();
+            pixels.pixels().map(|pixel| pixel[0] as f64).sum::<f64>() / pixels.len() as f64
+        };
+
+        let plain = Quantized::new(&image, 2, false);
+        let dithered = Quantized::new(&image, 2, true);
+        assert_eq!(dithered.indices.len(), 64 * 64);
+        assert!((mean(&dithered) - 128.0).abs() < 8.0, "{}", mean(&dithered));
+        assert!((mean(&dithered) - 128.0).abs() < (mean(&plain) - 128.0).abs());
+    }
+
+    #[test]
+    fn encodes_the_palette_and_its_alpha() {
+        let quantized = Quantized::new(&quarters(), 4, false);
+        let decoded = image::load_from_memory_with_format(&quantized.encode().unwrap(), ImageFormat::Png).unwrap();
+        assert_eq!(decoded.dimensions(), (32, 32));
+        assert_eq!(decoded.to_rgba8(), quantized.to_image().to_rgba8());
+        assert_eq!(decoded.to_rgba8().get_pixel(31, 31)[3], 0);
+    }
+
+    #[test]
+    fn drops_the_unused_colours() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, Rgba([10, 20, 30, 255])));
+        let quantized = Quantized::new(&image, 256, true);
+        assert_eq!(quantized.colors(), 1);
+        assert!(quantized.indices.iter().all(|index| *index == 0));
+    }
+}
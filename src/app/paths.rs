@@ -14,4 +14,41 @@ impl Paths{
     pub fn get_destination(&self)-> &Path{
         self.destination.strip_prefix("C:\\").unwrap_or(&self.destination)
     }
-}
\ No newline at end of file
+}
+
+/// Whether both paths lead to the same file, through links and relative parts too.
+pub(crate) fn is_same_file(path: &Path, other: &Path) -> bool {
+    if path == other {
+        return true;
+    }
+    match (path.canonicalize(), other.canonicalize()) {
+        (Ok(path), Ok(other)) => path == other,
+        _ => false,
+    }
+}
+
+/// Folder holding the settings of the application, `%APPDATA%\squooshies` on Windows
+/// and `$XDG_CONFIG_HOME/squooshies` or `~/.config/squooshies` elsewhere.
+pub fn config_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.unwrap_or_default().join("squooshies")
+}
+
+/// Folder holding what the application can generate again, `%LOCALAPPDATA%\squooshies` on Windows
+/// and `$XDG_CACHE_HOME/squooshies` or `~/.cache/squooshies` elsewhere.
+pub fn cache_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.unwrap_or_default().join("squooshies")
+}
@@ -4,26 +4,54 @@ use std::path::PathBuf;
 use std::path::Path;
 use std::time::SystemTime;
 use image::DynamicImage;
-use crate::error::TransformationError;
+use crate::app::format::Format;
+use crate::app::manifest::Output;
+use crate::app::resize::Resize;
+use crate::app::rotate::Rotate;
+use crate::error::{Stage, TransformationError};
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct Picture {
     pub path: PathBuf,
     pub name: FileName,
     pub is_selected: bool,
-    pub is_in_process: bool,
-    pub is_processed: bool,
+    pub status: Status,
+    pub outputs: Vec<Output>,
     pub metadata: Metadata, 
+    pub overrides: Overrides,
+    /// Small copy shown by the grid, set once generated.
+    pub thumbnail: Option<PathBuf>,
 }
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct FileName {
+    pub directory: PathBuf,
     pub source_name: String,
     pub prefix: String,
     pub suffix: String,
 }
 
 
+/// Where the picture stands in the current batch.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Status {
+    #[default]
+    Idle,
+    Queued,
+    Processing,
+    Done,
+    Failed(String),
+    Skipped,
+}
+
+/// Settings of a single picture replacing the ones of the batch.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Overrides {
+    pub resize: Option<Resize>,
+    pub format: Option<Format>,
+    pub rotate: Option<Rotate>,
+}
+
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct Metadata {
     pub weight: u64,
@@ -36,11 +64,15 @@ pub struct Metadata {
 }
 
 impl Picture {
-    pub fn new(path: PathBuf) -> Self{
+    /// Reads the metadata of the file at `path`, failing when it can not be read, such as once deleted.
+    pub fn new(path: PathBuf, root: &Path) -> Result<Self, TransformationError>{
         let file = path.file_stem().unwrap_or_default().to_str().unwrap_or_default();
-        let name = FileName::new(file);
-        let metadata = Metadata::new(&path);
-        Self { path, name, metadata,  is_selected:  true, is_in_process: false, is_processed: false }
+        let mut name = FileName::new(file);
+        if let Some(parent) = path.parent() {
+            name.directory = parent.strip_prefix(root).unwrap_or(Path::new("")).to_path_buf();
+        }
+        let metadata = Metadata::new(&path).map_err(|e| TransformationError::new(Stage::Load, e).with_input(&path))?;
+        Ok(Self { path, name, metadata,  is_selected:  true, status: Status::Idle, outputs: Vec::new(), overrides: Overrides::default(), thumbnail: None })
     }
     pub fn get_name(&self) -> String {
         self.path.file_name().unwrap_or_default().to_str().unwrap_or_default().to_string()
@@ -51,14 +83,7 @@ impl Picture {
     }
 
     pub fn get_weight(&self) -> String {
-        let weight = self.metadata.weight;
-        if weight > 1048576 {
-            format!("{:.2} MB", weight as f32 / 1024.0 / 1024.0)
-        } else if weight > 1024 {
-            format!("{:.2} KB", weight as f32 / 1024.0)
-        } else {
-            format!("{} B", weight)
-        }
+        format_weight(self.metadata.weight)
     }
 
     pub fn get_path(&self) -> &str{
@@ -69,22 +94,72 @@ impl Picture {
             .unwrap_or(self.path.to_str().unwrap_or_default())
     }
 
+    pub fn get_thumbnail(&self) -> Option<&str> {
+        let thumbnail = self.thumbnail.as_ref()?.to_str()?;
+        Some(thumbnail.strip_prefix("C:\\").unwrap_or(thumbnail))
+    }
 
     pub(crate) fn load(&self) -> Result<DynamicImage, TransformationError> {
-        image::open(self.path.to_str().unwrap_or_default()).map_err(|e| TransformationError::Image(e.to_string()))
+        image::open(&self.path)
+            .map_err(|e| TransformationError::new(Stage::Load, e).with_input(&self.path))
+    }
+}
+
+pub fn format_weight(weight: u64) -> String {
+    if weight > 1048576 {
+        format!("{:.2} MB", weight as f32 / 1024.0 / 1024.0)
+    } else if weight > 1024 {
+        format!("{:.2} KB", weight as f32 / 1024.0)
+    } else {
+        format!("{} B", weight)
+    }
+}
+
+impl Status {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Status::Idle => "",
+            Status::Queued => "Queued",
+            Status::Processing => "Processing",
+            Status::Done => "Done",
+            Status::Failed(_) => "Failed",
+            Status::Skipped => "Skipped",
+        }
+    }
+}
+
+impl Overrides {
+    pub fn is_empty(&self) -> bool {
+        self.resize.is_none() && self.format.is_none() && self.rotate.is_none()
+    }
+
+    pub fn get_resize<'a>(&'a self, global: &'a Resize) -> &'a Resize {
+        self.resize.as_ref().unwrap_or(global)
+    }
+
+    pub fn get_format<'a>(&'a self, global: &'a Format) -> &'a Format {
+        self.format.as_ref().unwrap_or(global)
+    }
+
+    pub fn get_rotate<'a>(&'a self, global: &'a Rotate) -> &'a Rotate {
+        self.rotate.as_ref().unwrap_or(global)
     }
 }
 
 impl FileName{
     pub fn new(name: &str) -> Self{
-        Self{source_name : name.to_string(), prefix: String::new(), suffix: String::new()}
+        Self{directory: PathBuf::new(), source_name : name.to_string(), prefix: String::new(), suffix: String::new()}
     }
-    
+
+    /// Output path of the picture, mirroring its sub folder of the source under `path`.
     pub fn build(&self, path: &Path) -> PathBuf {
-        path.to_owned()
-            .join(&self.prefix)
-            .join(&self.source_name)
-            .join(&self.suffix)
+        self.build_variant(path, "")
+    }
+
+    /// Output path of a variant of the picture, such as `photo-960w` for a `-960w` suffix.
+    pub fn build_variant(&self, path: &Path, suffix: &str) -> PathBuf {
+        path.join(&self.directory)
+            .join(format!("{}{}{}{}", self.prefix, self.source_name, self.suffix, suffix))
     }
 }
 #[cfg(target_os = "linux")]
@@ -94,10 +169,10 @@ use exif::{Exif, In, Tag};
 impl Metadata{
 
     #[cfg(target_os = "linux")]
-    pub fn new(path: &Path) -> Self {
-        let metadata = path.metadata().unwrap();
+    pub fn new(path: &Path) -> Result<Self, std::io::Error> {
+        let metadata = path.metadata()?;
         let (width, height) = get_image_size(path);
-        Self {
+        Ok(Self {
             weight: metadata.size(),
             width,
             height, 
@@ -105,14 +180,14 @@ impl Metadata{
             created: metadata.created().ok(),
             modified: metadata.modified().ok(),
             accessed: metadata.accessed().ok(),
-        }
+        })
     }
 
     #[cfg(target_os = "windows")]
-    pub fn new(path: &Path) -> Self {
-        let metadata = path.metadata().unwrap();
+    pub fn new(path: &Path) -> Result<Self, std::io::Error> {
+        let metadata = path.metadata()?;
         let (width, height) = get_image_size(path);
-        Self {
+        Ok(Self {
             weight: metadata.len(),
             width,
             height,
@@ -120,7 +195,7 @@ impl Metadata{
             created: metadata.created().ok(),
             modified: metadata.modified().ok(),
             accessed: metadata.accessed().ok(),
-        }
+        })
     }
 }
 
@@ -131,7 +206,7 @@ fn get_image_size(path: &Path) -> (usize, usize){
     }
 }
 fn get_rotation_code(path: &Path) -> Option<u32> {
-    let file = File::open(path).expect("Could not open file");
+    let file = File::open(path).ok()?;
     let mut bufreader = BufReader::new(file);
     let exifreader = exif::Reader::new();
     if let Ok(exif) = exifreader.read_from_container(&mut bufreader) {
@@ -142,11 +217,5 @@ fn get_rotation_code(path: &Path) -> Option<u32> {
 
 fn read_exif(exif: Exif) -> Option<u32> {
     let orientation = exif.get_field(Tag::Orientation, In::PRIMARY);
-    match orientation {
-        Some(orientation) => {
-            let orientation = orientation.value.get_uint(0).unwrap();
-            Some(orientation)
-        }
-        None => None,
-    }
+    orientation.and_then(|orientation| orientation.value.get_uint(0))
 }
@@ -0,0 +1,110 @@
+use std::num::NonZeroU8;
+use image::{DynamicImage, ImageFormat};
+use oxipng::{Deflaters, Options, StripChunks};
+use serde::{Deserialize, Serialize};
+use crate::app::format::encode_with_format;
+use crate::app::palette::Quantized;
+use crate::error::{Stage, TransformationError};
+
+/// Highest effort, the one of the slowest oxipng preset.
+pub const MAX_EFFORT: u8 = 6;
+const ZOPFLI_ITERATIONS: u8 = 15;
+
+/// Lossless optimisation of the PNG outputs, the pixels written are the same ones.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Png {
+    pub optimize: bool,
+    /// From 0 to 6, higher efforts try more compression levels and filter strategies.
+    pub effort: u8,
+    /// Lowers the bit depth and colour type when the pixels allow it, such as RGBA to RGB when fully opaque
+    /// or RGB to a palette with 256 colours or less.
+    pub reduce: bool,
+    /// Removes the metadata chunks that do not change how the picture is shown.
+    pub strip: bool,
+    /// Compresses with Zopfli, a few percent smaller for a much slower encoding.
+    pub zopfli: bool,
+    /// Reduces the picture to a palette of the fewest colours whose SSIM, in percent, reaches the quality, pngquant style.
+    pub palette: bool,
+    pub dithering: bool,
+    /// Lowest SSIM, in percent, a palette has to reach, pictures it cannot render well enough keep all their colours.
+    pub min_quality: u8,
+}
+
+impl Default for Png {
+    fn default() -> Self {
+        Self { optimize: true, effort: 2, reduce: true, strip: true, zopfli: false, palette: false, dithering: true, min_quality: 0 }
+    }
+}
+
+impl Png {
+    pub fn set_effort(&mut self, value: String) {
+        self.effort = value.parse::<u8>().unwrap_or(2).min(MAX_EFFORT);
+    }
+
+    /// Encodes `image`, along with the colours of its palette when it was reduced to one.
+    pub fn encode(&self, image: &DynamicImage, quality: u8) -> Result<(Vec<u8>, Option<u16>), TransformationError> {
+        // An empty picture has no colour to put in a palette, and PNG does not allow an empty one.
+        let is_empty = image.width() == 0 || image.height() == 0;
+        if self.palette && !is_empty {
+            let (quantized, score) = Quantized::fewest(image, quality, self.dithering);
+            if score * 100.0 >= self.min_quality as f64 {
+                let bytes = self.optimized(quantized.encode()?)?;
+                return Ok((bytes, Some(quantized.colors() as u16)));
+            }
+        }
+        Ok((self.optimized(encode_with_format(image, ImageFormat::Png)?)?, None))
+    }
+
+    fn optimized(&self, bytes: Vec<u8>) -> Result<Vec<u8>, TransformationError> {
+        if !self.optimize {
+            return Ok(bytes);
+        }
+        self.optimize(&bytes)
+    }
+
+    pub fn optimize(&self, bytes: &[u8]) -> Result<Vec<u8>, TransformationError> {
+        let mut options = Options::from_preset(self.effort.min(MAX_EFFORT));
+        options.bit_depth_reduction = self.reduce;
+        options.color_type_reduction = self.reduce;
+        options.palette_reduction = self.reduce;
+        options.grayscale_reduction = self.reduce;
+        options.strip = if self.strip { StripChunks::Safe } else { StripChunks::None };
+        if self.zopfli {
+            if let Some(iterations) = NonZeroU8::new(ZOPFLI_ITERATIONS) {
+                options.deflate = Deflaters::Zopfli { iterations };
+            }
+        }
+
+        oxipng::optimize_from_memory(bytes, &options)
+            .map_err(|err| TransformationError::new(Stage::Format, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+
+    #[test]
+    fn skips_the_palette_of_an_empty_picture() {
+        // No palette is made of a picture without pixels, the PNG encoder then rejects the picture itself.
+        let png = Png { palette: true, ..Png::default() };
+        let err = png.encode(&DynamicImage::ImageRgba8(RgbaImage::new(0, 0)), 90).unwrap_err();
+        assert_eq!(err.stage, Stage::Format);
+    }
+
+    #[test]
+    fn falls_back_to_lossless_under_the_min_quality() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, y| image::Rgba([(x * 8) as u8, (y * 8) as u8, ((x * y) % 256) as u8, 255])));
+        let png = Png { palette: true, min_quality: 100, ..Png::default() };
+        let (bytes, colors) = png.encode(&image, 90).unwrap();
+        assert_eq!(colors, None);
+        let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap();
+        assert_eq!(decoded.dimensions(), (32, 32));
+        assert_eq!(decoded.to_rgba8(), image.to_rgba8());
+
+        let png = Png { palette: true, ..Png::default() };
+        assert!(png.encode(&image, 50).unwrap().1.is_some());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::app::application::Application;
+use crate::app::format::Format;
+use crate::app::paths::config_dir;
+use crate::app::resize::Resize;
+use crate::app::rotate::Rotate;
+use crate::app::variants::Variants;
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "gui")]
+use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
+
+/// Named processing settings, stored as a TOML file in the presets folder.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub resize: Resize,
+    pub format: Format,
+    pub rotate: Rotate,
+    pub variants: Variants,
+}
+
+impl Preset {
+    pub fn from_app(name: &str, app: &Application) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            resize: app.resize.clone(),
+            format: app.format.clone(),
+            rotate: app.rotate.clone(),
+            variants: app.variants.clone(),
+        }
+    }
+
+    pub fn apply(&self, app: &mut Application) {
+        app.resize = self.resize.clone();
+        app.format = self.format.clone();
+        app.rotate = self.rotate.clone();
+        app.variants = self.variants.clone();
+    }
+
+    pub fn folder() -> PathBuf {
+        config_dir().join("presets")
+    }
+
+    /// Every preset saved in the presets folder, sorted by name.
+    pub fn list() -> Vec<Preset> {
+        let Ok(entries) = Self::folder().read_dir() else { return Vec::new() };
+        let mut presets: Vec<Preset> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|entry| Self::read(&entry.path()).ok())
+            .collect();
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        presets
+    }
+
+    pub fn find(name: &str) -> Result<Preset, Error> {
+        Self::list()
+            .into_iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No preset named {}", name)))
+    }
+
+    pub fn read(path: &Path) -> Result<Preset, Error> {
+        let content = std::fs::read_to_string(path)?;
+        let mut preset: Preset = toml::from_str(&content).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        if preset.name.is_empty() {
+            preset.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        }
+        Ok(preset)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let content = toml::to_string_pretty(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::write(path, content)
+    }
+
+    /// Saves the preset in the presets folder, replacing any preset with the same name.
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        if self.name.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "A preset needs a name"));
+        }
+        let folder = Self::folder();
+        std::fs::create_dir_all(&folder)?;
+        let path = folder.join(format!("{}.toml", self.get_file_name()));
+        self.write(&path)?;
+        Ok(path)
+    }
+
+    /// Copies a shared preset file into the presets folder.
+    pub fn import(path: &Path) -> Result<Preset, Error> {
+        let preset = Self::read(path)?;
+        preset.save()?;
+        Ok(preset)
+    }
+
+    fn get_file_name(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect()
+    }
+}
+
+/// Presets offered by the preset picker, along with the one currently applied.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Presets {
+    pub list: Vec<Preset>,
+    pub selected: String,
+}
+
+#[cfg(feature = "gui")]
+impl ToHtml for Presets {
+    fn to_html(&self) -> Element {
+        rsx! {
+            option { value: "", selected: self.selected.is_empty(), "Custom settings" }
+            for preset in self.list.iter() {
+                option { value: "{preset.name}", selected: preset.name == self.selected, {preset.name.clone()} }
+            }
+        }
+    }
+}
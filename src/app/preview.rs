@@ -0,0 +1,25 @@
+use crate::app::compare::Side;
+use crate::app::job::Job;
+use crate::app::progress::Cancel;
+use crate::error::TransformationError;
+use std::time::{Duration, Instant};
+
+/// The focused picture encoded at the current settings, nothing is written.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Preview {
+    pub name: String,
+    pub output: Side,
+    pub elapsed: Duration,
+}
+
+impl Preview {
+    pub fn new(job: &Job, cancel: &Cancel) -> Result<Self, TransformationError> {
+        let started = Instant::now();
+        let output = Side::encode(job, cancel)?;
+        Ok(Self { name: job.get_name(), output, elapsed: started.elapsed() })
+    }
+
+    pub fn get_summary(&self) -> String {
+        format!("{} · encoded in {} ms", self.output.get_summary(), self.elapsed.as_millis())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::app::manifest::Output;
+use crate::app::picture::Status;
+use crate::error::{Stage, TransformationError};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Sent by the transformation each time a picture changes status.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Progress {
+    pub path: PathBuf,
+    pub status: Status,
+    /// Files written for the picture once it is done.
+    pub outputs: Vec<Output>,
+}
+
+/// A picture the batch could not transform, its path is the input of the error.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Failure {
+    pub name: String,
+    pub error: TransformationError,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+/// Counts of the running batch, along with when it started to estimate the time left.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BatchProgress {
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub started: Option<Instant>,
+    pub is_cancelled: bool,
+}
+
+/// Shared flag asking a running batch to stop, checked between pictures and between encodes.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fails as cancelled at `stage`, the step that would have run next.
+    pub fn check(&self, stage: Stage) -> Result<(), TransformationError> {
+        if self.is_cancelled() {
+            return Err(TransformationError::cancelled(stage));
+        }
+        Ok(())
+    }
+}
+
+impl BatchProgress {
+    pub fn start(total: usize) -> Self {
+        Self { total, started: Some(Instant::now()), ..Self::default() }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.done + self.failed + self.skipped
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some() && self.finished() < self.total
+    }
+
+    pub fn get_percent(&self) -> usize {
+        if self.total == 0 {
+            return 0;
+        }
+        self.finished() * 100 / self.total
+    }
+
+    /// Time left at the average pace of the pictures finished so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let started = self.started?;
+        let finished = self.finished() as u32;
+        if finished == 0 {
+            return None;
+        }
+        let left = (self.total as u32).saturating_sub(finished);
+        Some(started.elapsed() / finished * left)
+    }
+
+    pub fn get_summary(&self) -> String {
+        let mut summary = format!("{} / {} done", self.done, self.total);
+        if self.failed > 0 {
+            summary += &format!(", {} failed", self.failed);
+        }
+        if self.skipped > 0 {
+            summary += &format!(", {} skipped", self.skipped);
+        }
+        if self.is_running() && self.is_cancelled {
+            summary += ", cancelling";
+        } else if self.is_running() {
+            if let Some(eta) = self.eta() {
+                let seconds = eta.as_secs();
+                summary += &format!(", about {}m {:02}s left", seconds / 60, seconds % 60);
+            }
+        }
+        summary
+    }
+}
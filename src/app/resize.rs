@@ -1,9 +1,14 @@
-use dioxus::prelude::*;
 use image::DynamicImage;
 use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use crate::error::{ErrorKind, Stage, TransformationError};
+#[cfg(feature = "gui")]
 use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Resize {
     pub resize_type: Option<ResizeType>,
     pub method: ResizeMethod,
@@ -31,18 +36,24 @@ impl Resize{
         }
     }
     
-    pub fn apply(&self, image: &mut DynamicImage){
+    pub fn apply(&self, image: &mut DynamicImage) -> Result<(), TransformationError>{
         if let Some(resize_type) = &self.resize_type{
+            if self.width == 0 || self.height == 0 {
+                let size = ErrorKind::InvalidSize { width: self.width, height: self.height };
+                return Err(TransformationError::new(Stage::Resize, size));
+            }
             match resize_type{
                 ResizeType::Fill => {*image = image.resize_to_fill(self.width, self.height, self.method.to_image_filter());}
-                ResizeType::Exact => {*image = image.resize_exact(self.height, self.height, self.method.to_image_filter());}
+                ResizeType::Exact => {*image = image.resize_exact(self.width, self.height, self.method.to_image_filter());}
                 ResizeType::Thumbnail => {*image = image.thumbnail(self.width, self.height);}
             }
         }
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ResizeType{
     #[default]
     Exact,
@@ -50,13 +61,21 @@ pub enum ResizeType{
     Thumbnail
 }
 
-const RESIZE_TYPES: [(&str, &str); 3] = [("exact", "Exact"), ("fill", "Fill"), ("thumbnail", "Thumbnail")];
+#[cfg(feature = "gui")]
+const RESIZE_TYPES: [(&str, &str); 4] = [("none", "No resizing"), ("exact", "Exact"), ("fill", "Fill"), ("thumbnail", "Thumbnail")];
 
-impl ToHtml for ResizeType{
+#[cfg(feature = "gui")]
+impl ToHtml for Resize{
     fn to_html(&self) -> Element {
+        let selected = match self.resize_type {
+            Some(ResizeType::Exact) => "exact",
+            Some(ResizeType::Fill) => "fill",
+            Some(ResizeType::Thumbnail) => "thumbnail",
+            None => "none"
+        };
         rsx!{
             for (value , label) in RESIZE_TYPES.iter() {
-                option { value: "{value}", {label} }
+                option { value: "{value}", selected: *value == selected, {label} }
             }
         }
     }
@@ -74,7 +93,8 @@ impl Default for Resize{
 }
 
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ResizeMethod{
     #[default]
     Lanczos3,
@@ -96,13 +116,22 @@ impl ResizeMethod{
     }
 }
 
+#[cfg(feature = "gui")]
 const RESIZE_METHODS: [(&str, &str); 5] = [("lanczos3", "Lanczos3"), ("nearest", "Nearest"), ("catmullrom", "CatmullRom"), ("triangle", "Triangle"), ("gaussian", "Gaussian")];
 
+#[cfg(feature = "gui")]
 impl ToHtml for ResizeMethod{
     fn to_html(&self) -> Element {
+        let selected = match self {
+            ResizeMethod::Lanczos3 => "lanczos3",
+            ResizeMethod::Nearest => "nearest",
+            ResizeMethod::CatmullRom => "catmullrom",
+            ResizeMethod::Triangle => "triangle",
+            ResizeMethod::Gaussian => "gaussian"
+        };
         rsx!{
             for (value , label) in RESIZE_METHODS.iter() {
-                option { value: "{value}", {label} }
+                option { value: "{value}", selected: *value == selected, {label} }
             }
         }
     }
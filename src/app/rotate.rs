@@ -1,16 +1,19 @@
-use std::fs::File;
-use std::io::BufReader;
-use std::path::{Path, PathBuf};
-use dioxus::prelude::*;
-use exif::{Exif, In, Tag};
 use image::DynamicImage;
+use image::metadata::Orientation;
+use serde::{Deserialize, Serialize};
+use crate::error::TransformationError;
+#[cfg(feature = "gui")]
 use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
 
-#[derive(Clone, PartialEq, Debug, Default)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Rotate{
     pub angle: Angle,
 }
 
+#[cfg(feature = "gui")]
 impl ToHtml for Rotate{
     fn to_html(&self) -> Element {
         rsx!{
@@ -30,7 +33,9 @@ impl Rotate{
         }
     }
 
-    pub fn apply(&self, image: &mut DynamicImage, rotation_code: Option<u32>){
+    /// Orients the picture from its EXIF code, then turns it by the angle.
+    /// Returns a `Result` like the other steps of the pipeline, although no rotation fails.
+    pub fn apply(&self, image: &mut DynamicImage, rotation_code: Option<u32>) -> Result<(), TransformationError>{
         set_initial_rotation(rotation_code, image);
         match self.angle{
             Angle::None => {},
@@ -38,20 +43,25 @@ impl Rotate{
             Angle::Half => {*image =  image.rotate180();},
             Angle::ThreeQuarters => {*image =  image.rotate270();}
         }
+        Ok(())
     }
 }
 
+/// Orients the picture as its EXIF `code` says it is seen, the mirrored orientations 2, 4, 5 and 7 included.
+/// Codes outside 1 to 8 leave it as stored.
 pub fn set_initial_rotation(code: Option<u32>, picture: &mut DynamicImage) {
-    match code {
-        Some(6) => { *picture = picture.rotate90(); }
-        Some(3) => { *picture = picture.rotate180(); }
-        Some(8) => { *picture = picture.rotate270(); }
-        _ => {}
+    let orientation = code
+        .and_then(|code| u8::try_from(code).ok())
+        .and_then(Orientation::from_exif);
+    if let Some(orientation) = orientation {
+        picture.apply_orientation(orientation);
     }
 }
 
+#[cfg(feature = "gui")]
 const OPTIONS: [(&str, &str); 4] = [("none", "0 deg"), ("90", "90 deg"), ("180", "180 deg"), ("270", "270 deg")];
-#[derive(Clone, PartialEq, Debug, Default)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Angle{
     #[default]
     None,
@@ -60,12 +70,70 @@ pub enum Angle{
     ThreeQuarters
 }
 
+#[cfg(feature = "gui")]
 impl ToHtml for Angle{
     fn to_html(&self) -> Element {
+        let selected = match self {
+            Angle::None => "none",
+            Angle::Quarter => "90",
+            Angle::Half => "180",
+            Angle::ThreeQuarters => "270"
+        };
         rsx!{
             for (value , label) in OPTIONS {
-                option { value, {label} }
+                option { value, selected: value == selected, {label} }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    /// 3×2 picture whose pixels are numbered from the top left corner, row by row.
+    fn numbered() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| Rgb([(y * 3 + x) as u8, 0, 0])))
+    }
+
+    fn rows(image: &DynamicImage) -> Vec<Vec<u8>> {
+        (0..image.height()).map(|y| (0..image.width()).map(|x| image.get_pixel(x, y)[0]).collect()).collect()
+    }
+
+    #[test]
+    fn applies_every_exif_orientation() {
+        let expected: [(u32, Vec<Vec<u8>>); 8] = [
+            (1, vec![vec![0, 1, 2], vec![3, 4, 5]]),
+            (2, vec![vec![2, 1, 0], vec![5, 4, 3]]),
+            (3, vec![vec![5, 4, 3], vec![2, 1, 0]]),
+            (4, vec![vec![3, 4, 5], vec![0, 1, 2]]),
+            (5, vec![vec![0, 3], vec![1, 4], vec![2, 5]]),
+            (6, vec![vec![3, 0], vec![4, 1], vec![5, 2]]),
+            (7, vec![vec![5, 2], vec![4, 1], vec![3, 0]]),
+            (8, vec![vec![2, 5], vec![1, 4], vec![0, 3]]),
+        ];
+        for (code, pixels) in expected {
+            let mut image = numbered();
+            set_initial_rotation(Some(code), &mut image);
+            assert_eq!(rows(&image), pixels, "orientation {}", code);
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_codes() {
+        for code in [None, Some(0), Some(9), Some(u32::MAX)] {
+            let mut image = numbered();
+            set_initial_rotation(code, &mut image);
+            assert_eq!(image, numbered());
+        }
+    }
+
+    #[test]
+    fn turns_after_orienting() {
+        let mut image = numbered();
+        let rotate = Rotate { angle: Angle::Half };
+        rotate.apply(&mut image, Some(6)).unwrap();
+        assert_eq!(rows(&image), [vec![2, 5], vec![1, 4], vec![0, 3]]);
+    }
+}
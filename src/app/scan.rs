@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "gui")]
+use crate::components::ToHtml;
+#[cfg(feature = "gui")]
+use dioxus::prelude::*;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Scan {
+    pub is_recursive: bool,
+    pub depth: u32,
+    pub symlinks: Symlinks,
+}
+
+impl Default for Scan {
+    fn default() -> Self {
+        Self { is_recursive: false, depth: 8, symlinks: Symlinks::Skip }
+    }
+}
+
+impl Scan {
+    pub fn set_recursive(&mut self, value: String) {
+        self.is_recursive = value == "recursive";
+    }
+
+    pub fn set_symlinks(&mut self, value: String) {
+        self.symlinks = match value.as_str() {
+            "follow" => Symlinks::Follow,
+            _ => Symlinks::Skip,
+        }
+    }
+
+    /// Lists every file under `root`, descending at most `depth` folders when recursive.
+    /// The `skip` folder is never entered, so an output folder nested in the source is not rescanned.
+    pub fn files(&self, root: &Path, skip: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+        let depth = if self.is_recursive { self.depth } else { 0 };
+        let mut visited = HashSet::new();
+        if let Ok(root) = root.canonicalize() {
+            visited.insert(root);
+        }
+
+        let skip = skip.canonicalize().unwrap_or_default();
+        let mut files = Vec::new();
+        for entry in root.read_dir()?.flatten() {
+            self.visit(entry.path(), depth, &skip, &mut visited, &mut files);
+        }
+        Ok(files)
+    }
+
+    /// Whether `path`, found under `root` some other way than `files`, goes through links `files` would have skipped.
+    pub fn is_linked(&self, root: &Path, path: &Path) -> bool {
+        if self.symlinks == Symlinks::Follow {
+            return false;
+        }
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != root)
+            .any(|ancestor| ancestor.symlink_metadata().is_ok_and(|link| link.file_type().is_symlink()))
+    }
+
+    fn visit(&self, path: PathBuf, depth: u32, skip: &Path, visited: &mut HashSet<PathBuf>, files: &mut Vec<PathBuf>) {
+        let Ok(link) = path.symlink_metadata() else { return };
+        if link.file_type().is_symlink() && self.symlinks == Symlinks::Skip {
+            return;
+        }
+
+        let Ok(metadata) = path.metadata() else { return };
+        if metadata.is_file() {
+            files.push(path);
+            return;
+        }
+        if !metadata.is_dir() || depth == 0 {
+            return;
+        }
+
+        // A followed link can point back to one of its parents, only enter each folder once.
+        let Ok(canonical) = path.canonicalize() else { return };
+        if canonical == skip || !visited.insert(canonical) {
+            return;
+        }
+
+        if let Ok(entries) = path.read_dir() {
+            for entry in entries.flatten() {
+                self.visit(entry.path(), depth - 1, skip, visited, files);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+const RECURSION: [(&str, &str); 2] = [("top", "Top folder only"), ("recursive", "Include subfolders")];
+
+#[cfg(feature = "gui")]
+impl ToHtml for Scan {
+    fn to_html(&self) -> Element {
+        let selected = if self.is_recursive { "recursive" } else { "top" };
+        rsx! {
+            for (value , label) in RECURSION {
+                option { value, selected: value == selected, {label} }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Symlinks {
+    #[default]
+    Skip,
+    Follow,
+}
+
+#[cfg(feature = "gui")]
+const SYMLINKS: [(&str, &str); 2] = [("skip", "Skip links"), ("follow", "Follow links")];
+
+#[cfg(feature = "gui")]
+impl ToHtml for Symlinks {
+    fn to_html(&self) -> Element {
+        let selected = match self {
+            Symlinks::Skip => "skip",
+            Symlinks::Follow => "follow",
+        };
+        rsx! {
+            for (value , label) in SYMLINKS {
+                option { value, selected: value == selected, {label} }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Folder tree in the temporary directory, removed once dropped.
+    struct Tree(PathBuf);
+
+    impl Tree {
+        fn new(name: &str, files: &[&str]) -> Self {
+            let root = std::env::temp_dir().join(format!("scan-test-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            for file in files {
+                let path = root.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, b"").unwrap();
+            }
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+
+        /// Files found by `scan`, relative to the root and sorted.
+        fn files(&self, scan: &Scan, skip: &Path) -> Vec<String> {
+            let mut files = scan
+                .files(&self.0, skip)
+                .unwrap()
+                .iter()
+                .map(|file| file.strip_prefix(&self.0).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const FILES: [&str; 4] = ["a.png", "one/b.png", "one/two/c.png", "one/two/three/d.png"];
+
+    #[test]
+    fn lists_the_top_folder_only() {
+        let tree = Tree::new("top", &FILES);
+        assert_eq!(tree.files(&Scan::default(), Path::new("")), ["a.png"]);
+    }
+
+    #[test]
+    fn limits_the_depth() {
+        let tree = Tree::new("depth", &FILES);
+        let scan = |depth| Scan { is_recursive: true, depth, ..Scan::default() };
+        assert_eq!(tree.files(&scan(0), Path::new("")), ["a.png"]);
+        assert_eq!(tree.files(&scan(2), Path::new("")), ["a.png", "one/b.png", "one/two/c.png"]);
+        assert_eq!(tree.files(&scan(8), Path::new("")), FILES);
+    }
+
+    #[test]
+    fn skips_the_output_folder() {
+        let tree = Tree::new("skip", &FILES);
+        let scan = Scan { is_recursive: true, ..Scan::default() };
+        assert_eq!(tree.files(&scan, &tree.0.join("one/two")), ["a.png", "one/b.png"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_links_only_when_asked() {
+        let tree = Tree::new("links", &["a.png", "top/b.png"]);
+        let elsewhere = Tree::new("links-target", &["c.png"]);
+        std::os::unix::fs::symlink(&elsewhere.0, tree.0.join("top/linked")).unwrap();
+
+        let scan = Scan { is_recursive: true, ..Scan::default() };
+        assert_eq!(tree.files(&scan, Path::new("")), ["a.png", "top/b.png"]);
+        assert!(scan.is_linked(&tree.0, &tree.0.join("top/linked/c.png")));
+
+        let scan = Scan { symlinks: Symlinks::Follow, ..scan };
+        assert_eq!(tree.files(&scan, Path::new("")), ["a.png", "top/b.png", "top/linked/c.png"]);
+        assert!(!scan.is_linked(&tree.0, &tree.0.join("top/linked/c.png")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn enters_each_folder_once() {
+        let tree = Tree::new("loop", &["a.png", "one/b.png"]);
+        std::os::unix::fs::symlink(&tree.0, tree.0.join("one/back")).unwrap();
+
+        let scan = Scan { is_recursive: true, symlinks: Symlinks::Follow, ..Scan::default() };
+        assert_eq!(tree.files(&scan, Path::new("")), ["a.png", "one/b.png"]);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::app::picture::Picture;
+use std::cmp::Reverse;
+#[cfg(feature = "gui")]
 use crate::components::ToHtml;
+#[cfg(feature = "gui")]
 use dioxus::prelude::*;
 #[derive(Clone, PartialEq, Debug)]
 pub struct Sort{
@@ -15,18 +18,18 @@ impl Default for Sort {
 }
 
 impl Sort {
-    pub fn apply(&self, pictures: &mut Vec<Picture>) {
+    pub fn apply(&self, pictures: &mut [Picture]) {
         match (&self.field, &self.order) {
-            (SortType::Name, SortOrder::Asc) => pictures.sort_by(|a, b| a.get_name().cmp(&b.get_name())),
-            (SortType::Name, SortOrder::Desc) => pictures.sort_by(|a, b| b.get_name().cmp(&a.get_name())),
-            (SortType::Weight, SortOrder::Asc) => pictures.sort_by(|a, b| a.metadata.weight.cmp(&b.metadata.weight)),
-            (SortType::Weight, SortOrder::Desc) => pictures.sort_by(|a, b| b.metadata.weight.cmp(&a.metadata.weight)),
-            (SortType::Created, SortOrder::Asc) => pictures.sort_by(|a,b | a.metadata.created.cmp(&b.metadata.created)),
-            (SortType::Created, SortOrder::Desc) => pictures.sort_by(|a,b | b.metadata.created.cmp(&a.metadata.created)),
-            (SortType::Modified, SortOrder::Asc) => pictures.sort_by(|a, b| a.metadata.modified.cmp(&b.metadata.modified)),
-            (SortType::Modified, SortOrder::Desc) => pictures.sort_by(|a, b| b.metadata.modified.cmp(&a.metadata.modified)),
-            (SortType::Accessed, SortOrder::Asc) => pictures.sort_by(|a, b| a.metadata.accessed.cmp(&b.metadata.accessed)),
-            (SortType::Accessed, SortOrder::Desc) => pictures.sort_by(|a, b| b.metadata.accessed.cmp(&a.metadata.accessed)),
+            (SortType::Name, SortOrder::Asc) => pictures.sort_by_key(|picture| picture.get_name()),
+            (SortType::Name, SortOrder::Desc) => pictures.sort_by_key(|picture| Reverse(picture.get_name())),
+            (SortType::Weight, SortOrder::Asc) => pictures.sort_by_key(|picture| picture.metadata.weight),
+            (SortType::Weight, SortOrder::Desc) => pictures.sort_by_key(|picture| Reverse(picture.metadata.weight)),
+            (SortType::Created, SortOrder::Asc) => pictures.sort_by_key(|picture| picture.metadata.created),
+            (SortType::Created, SortOrder::Desc) => pictures.sort_by_key(|picture| Reverse(picture.metadata.created)),
+            (SortType::Modified, SortOrder::Asc) => pictures.sort_by_key(|picture| picture.metadata.modified),
+            (SortType::Modified, SortOrder::Desc) => pictures.sort_by_key(|picture| Reverse(picture.metadata.modified)),
+            (SortType::Accessed, SortOrder::Asc) => pictures.sort_by_key(|picture| picture.metadata.accessed),
+            (SortType::Accessed, SortOrder::Desc) => pictures.sort_by_key(|picture| Reverse(picture.metadata.accessed)),
         }
     }
     
@@ -53,13 +56,22 @@ pub enum SortType{
     Accessed
 }
 
+#[cfg(feature = "gui")]
 const SORT_TYPE : [(&str, &str); 5] = [("Name", "name"), ("Weight", "weight"), ("Created", "created"), ("Modified", "modified"), ("Accessed", "accessed")]; 
 
+#[cfg(feature = "gui")]
 impl ToHtml for SortType {
     fn to_html(&self) -> Element {
+        let selected = match self {
+            SortType::Name => "name",
+            SortType::Weight => "weight",
+            SortType::Created => "created",
+            SortType::Modified => "modified",
+            SortType::Accessed => "accessed",
+        };
         rsx!{
             for (label , value) in SORT_TYPE.iter() {
-                option { value: *value, {label} }
+                option { value: *value, selected: *value == selected, {label} }
             }
         }
     }
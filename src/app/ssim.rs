@@ -0,0 +1,130 @@
+use image::{DynamicImage, GenericImageView, GrayImage, Luma};
+
+/// Side of the square windows compared, and the step between two of them.
+const WINDOW: u32 = 8;
+const STEP: u32 = 4;
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Structural similarity of the luma of two images of the same size, 1.0 meaning identical.
+/// Transparent images are composited over black and over white, the lower score being kept,
+/// so the colours hidden under transparent pixels do not count while a wrong alpha does.
+pub fn ssim(reference: &DynamicImage, distorted: &DynamicImage) -> f64 {
+    if reference.dimensions() != distorted.dimensions() {
+        return 0.0;
+    }
+    if !reference.color().has_alpha() && !distorted.color().has_alpha() {
+        return luma_ssim(&reference.to_luma8(), &distorted.to_luma8());
+    }
+    [u8::MIN, u8::MAX]
+        .into_iter()
+        .map(|background| luma_ssim(&composite(reference, background), &composite(distorted, background)))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Luma of `image` seen over a plain `background`.
+fn composite(image: &DynamicImage, background: u8) -> GrayImage {
+    let luma = image.to_luma_alpha8();
+    GrayImage::from_fn(luma.width(), luma.height(), |x, y| {
+        let [value, alpha] = luma.get_pixel(x, y).0;
+        let (value, alpha, background) = (value as u32, alpha as u32, background as u32);
+        Luma([((value * alpha + background * (255 - alpha) + 127) / 255) as u8])
+    })
+}
+
+/// The mean of the scores of overlapping windows, as described by Wang et al.
+fn luma_ssim(reference: &GrayImage, distorted: &GrayImage) -> f64 {
+    let (width, height) = reference.dimensions();
+    if width < WINDOW || height < WINDOW {
+        return window_ssim(reference, distorted, 0, 0, width, height);
+    }
+
+    let mut total = 0.0;
+    let mut count = 0;
+    for y in (0..=height - WINDOW).step_by(STEP as usize) {
+        for x in (0..=width - WINDOW).step_by(STEP as usize) {
+            total += window_ssim(reference, distorted, x, y, WINDOW, WINDOW);
+            count += 1;
+        }
+    }
+    total / count as f64
+}
+
+fn window_ssim(reference: &GrayImage, distorted: &GrayImage, x: u32, y: u32, width: u32, height: u32) -> f64 {
+    let n = (width * height) as f64;
+    let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for j in y..y + height {
+        for i in x..x + width {
+            let a = reference.get_pixel(i, j)[0] as f64;
+            let b = distorted.get_pixel(i, j)[0] as f64;
+            sum_a += a;
+            sum_b += b;
+            sum_aa += a * a;
+            sum_bb += b * b;
+            sum_ab += a * b;
+        }
+    }
+
+    let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+    let variance_a = sum_aa / n - mean_a * mean_a;
+    let variance_b = sum_bb / n - mean_b * mean_b;
+    let covariance = sum_ab / n - mean_a * mean_b;
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, ((x + y) * 4) as u8]))
+    }
+
+    #[test]
+    fn identical_images_score_one() {
+        let image = DynamicImage::ImageRgb8(gradient(32, 32));
+        assert!((ssim(&image, &image) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn different_sizes_score_zero() {
+        let image = DynamicImage::ImageRgb8(gradient(32, 32));
+        let other = DynamicImage::ImageRgb8(gradient(32, 16));
+        assert_eq!(ssim(&image, &other), 0.0);
+    }
+
+    #[test]
+    fn noise_lowers_the_score() {
+        let image = gradient(32, 32);
+        let mut noisy = image.clone();
+        for (i, pixel) in noisy.pixels_mut().enumerate() {
+            if i % 3 == 0 {
+                pixel.0 = [255 - pixel.0[0], pixel.0[1], 0];
+            }
+        }
+        let score = ssim(&DynamicImage::ImageRgb8(image), &DynamicImage::ImageRgb8(noisy));
+        assert!(score < 0.9, "{}", score);
+    }
+
+    #[test]
+    fn ignores_the_colour_of_transparent_pixels() {
+        let image = RgbaImage::from_fn(32, 32, |x, y| if x < 16 { Rgba([200, 50, 50, 255]) } else { Rgba([(x * y) as u8, 0, 0, 0]) });
+        let mut cleaned = image.clone();
+        for pixel in cleaned.pixels_mut().filter(|pixel| pixel[3] == 0) {
+            pixel.0 = [0, 0, 0, 0];
+        }
+        let score = ssim(&DynamicImage::ImageRgba8(image), &DynamicImage::ImageRgba8(cleaned));
+        assert!((score - 1.0).abs() < 1e-9, "{}", score);
+    }
+
+    #[test]
+    fn counts_a_wrong_alpha() {
+        let image = RgbaImage::from_fn(32, 32, |x, _| Rgba([255, 255, 255, if x < 16 { 255 } else { 0 }]));
+        let opaque = RgbaImage::from_pixel(32, 32, Rgba([255, 255, 255, 255]));
+        let score = ssim(&DynamicImage::ImageRgba8(image), &DynamicImage::ImageRgba8(opaque));
+        assert!(score < 0.9, "{}", score);
+    }
+}
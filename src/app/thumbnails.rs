@@ -0,0 +1,143 @@
+use crate::app::format::{encode_with_format, write_file};
+use crate::app::jpeg::Jpeg;
+use crate::app::paths::cache_dir;
+use crate::app::picture::Picture;
+use crate::app::rotate::set_initial_rotation;
+use crate::error::{Stage, TransformationError};
+use image::ImageFormat;
+use fnv::FnvHasher;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use rayon::ThreadPool;
+use std::fs::File;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shortest side of a thumbnail, twice the cards of the grid for high density screens.
+const SIZE: u32 = 640;
+const QUALITY: u8 = 80;
+/// Threads generating thumbnails, leaving the rest of the machine to the batch and the interface.
+const THREADS: usize = 2;
+/// Weight of the cache past which the least recently used thumbnails are removed.
+const MAX_WEIGHT: u64 = 256 * 1024 * 1024;
+
+/// Pool the thumbnails are generated on, built the first time it is needed.
+static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+/// When the first thumbnails were asked for, the ones used since may still be shown by the grid.
+static SESSION: OnceLock<SystemTime> = OnceLock::new();
+
+/// Small copies of the pictures shown by the grid instead of the originals.
+/// They are cached on disk under a key made of the path, modified time and weight of the picture,
+/// so a picture edited since gets a new one. The key is hashed with FNV, which gives the same key across Rust releases.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Thumbnails {
+    pub dir: PathBuf,
+}
+
+impl Default for Thumbnails {
+    fn default() -> Self {
+        Self { dir: cache_dir().join("thumbnails") }
+    }
+}
+
+impl Thumbnails {
+    /// Cached thumbnail of the picture, if it was already generated.
+    pub fn find(&self, picture: &Picture) -> Option<PathBuf> {
+        let key = self.key(picture);
+        ["jpg", "png"]
+            .into_iter()
+            .map(|extension| key.with_extension(extension))
+            .find(|path| path.is_file())
+    }
+
+    /// Cached thumbnail of the picture, generating it the first time.
+    /// It is oriented as the picture is seen, pictures with transparency keep it through a PNG.
+    /// A cached one is marked as used so it is the last to be evicted.
+    pub fn get(&self, picture: &Picture) -> Result<PathBuf, TransformationError> {
+        if let Some(path) = self.find(picture) {
+            let _ = File::options().write(true).open(&path).and_then(|file| file.set_modified(SystemTime::now()));
+            return Ok(path);
+        }
+
+        let mut image = picture.load()?;
+        set_initial_rotation(picture.metadata.rotation, &mut image);
+        if image.width().min(image.height()) > SIZE {
+            image = if image.width() < image.height() {
+                image.thumbnail(SIZE, u32::MAX)
+            } else {
+                image.thumbnail(u32::MAX, SIZE)
+            };
+        }
+
+        let (bytes, extension) = if image.color().has_alpha() {
+            (encode_with_format(&image, ImageFormat::Png)?, "png")
+        } else {
+            (Jpeg::fastest().encode(&image, QUALITY)?, "jpg")
+        };
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| TransformationError::new(Stage::Write, e).with_output(&self.dir))?;
+        let path = self.key(picture).with_extension(extension);
+        write_file(&path, &bytes).map_err(|e| e.with_input(&picture.path))?;
+        Ok(path)
+    }
+
+    /// Gets the thumbnail of every picture on a small pool of its own, calling `on_thumbnail` as each one is ready.
+    /// The cache is trimmed once they are all done, keeping the thumbnails used since the first call,
+    /// as the grid asks for them a few rows at a time and still shows the earlier ones.
+    pub fn get_all<F>(&self, pictures: &[Picture], on_thumbnail: F)
+    where
+        F: Fn(&Path, Result<PathBuf, TransformationError>) + Sync,
+    {
+        let started = *SESSION.get_or_init(SystemTime::now);
+        let generate = || {
+            pictures
+                .par_iter()
+                .for_each(|picture| on_thumbnail(&picture.path, self.get(picture)))
+        };
+        let pool = POOL.get_or_init(|| rayon::ThreadPoolBuilder::new().num_threads(THREADS).build().ok());
+        match pool {
+            Some(pool) => pool.install(generate),
+            None => generate(),
+        }
+        self.evict(MAX_WEIGHT, started);
+    }
+
+    /// Removes the least recently used thumbnails until the cache weighs at most `max_weight`,
+    /// those used since `kept_since` excepted as the grid is showing them.
+    /// Thumbnails that cannot be read or removed are left alone, they are regenerated when missing.
+    fn evict(&self, max_weight: u64, kept_since: SystemTime) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<(PathBuf, SystemTime, u64)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+                Some((entry.path(), metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len()))
+            })
+            .collect();
+        let mut weight: u64 = files.iter().map(|(_, _, len)| len).sum();
+        files.sort_by_key(|(_, modified, _)| *modified);
+        for (path, modified, len) in files {
+            if weight <= max_weight || modified >= kept_since {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                weight -= len;
+            }
+        }
+    }
+
+    fn key(&self, picture: &Picture) -> PathBuf {
+        let modified = picture.metadata.modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_nanos())
+            .unwrap_or_default();
+        let mut hasher = FnvHasher::default();
+        hasher.write(picture.path.to_string_lossy().as_bytes());
+        hasher.write(&modified.to_le_bytes());
+        hasher.write(&picture.metadata.weight.to_le_bytes());
+        self.dir.join(format!("{:016x}", hasher.finish()))
+    }
+}
@@ -0,0 +1,101 @@
+use crate::app::format::Format;
+use crate::app::resize::Resize;
+use crate::error::TransformationError;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Resized copies of a picture along with the suffix of their file name.
+pub type Resized = Vec<(DynamicImage, String)>;
+
+/// Outputs written for every picture, each width combined with each format.
+/// Without widths the resize settings are used, without formats the format settings are.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Variants {
+    pub widths: Vec<u32>,
+    pub formats: Vec<String>,
+}
+
+impl Variants {
+    pub fn set_widths(&mut self, value: String) {
+        self.widths = value
+            .split([',', ' '])
+            .filter_map(|width| width.trim().trim_end_matches('w').parse::<u32>().ok())
+            .filter(|width| *width > 0)
+            .collect();
+        self.widths.sort_unstable();
+        self.widths.dedup();
+    }
+
+    pub fn get_widths(&self) -> String {
+        self.widths.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+    }
+
+    pub fn set_formats(&mut self, value: String) {
+        self.formats = value
+            .split(',')
+            .map(|format| format.trim().to_string())
+            .filter(|format| !format.is_empty())
+            .collect();
+    }
+
+    pub fn toggle_format(&mut self, value: String) {
+        match self.formats.iter().position(|format| *format == value) {
+            Some(index) => { self.formats.remove(index); }
+            None => self.formats.push(value),
+        }
+    }
+
+    pub fn has_format(&self, value: &str) -> bool {
+        self.formats.iter().any(|format| format == value)
+    }
+
+    /// Resized copies of `image`, widths larger than the picture are skipped, it is never upscaled.
+    pub fn resize(&self, image: &DynamicImage, resize: &Resize) -> Result<Resized, TransformationError> {
+        if self.widths.is_empty() {
+            let mut image = image.clone();
+            resize.apply(&mut image)?;
+            return Ok(vec![(image, String::new())]);
+        }
+
+        let mut widths: Vec<u32> = self.widths.iter().copied().filter(|width| *width < image.width()).collect();
+        if widths.len() < self.widths.len() {
+            widths.push(image.width());
+        }
+
+        let resized = widths
+            .into_iter()
+            .map(|width| {
+                let resized = if width == image.width() {
+                    image.clone()
+                } else {
+                    image.resize(width, u32::MAX, resize.method.to_image_filter())
+                };
+                (resized, format!("-{}w", width))
+            })
+            .collect();
+        Ok(resized)
+    }
+
+    /// Formats to encode, "No reformating" keeping the format of the source file.
+    pub fn formats(&self, format: &Format, source: &Path) -> Vec<Format> {
+        let mut formats: Vec<Format> = if self.formats.is_empty() {
+            vec![format.clone()]
+        } else {
+            self.formats
+                .iter()
+                .map(|key| {
+                    let mut variant = format.clone();
+                    variant.set_format(key.clone());
+                    variant
+                })
+                .collect()
+        };
+
+        for variant in formats.iter_mut().filter(|variant| variant.image.is_none()) {
+            variant.image = ImageFormat::from_path(source).ok();
+        }
+        formats
+    }
+}
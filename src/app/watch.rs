@@ -0,0 +1,181 @@
+use crate::app::application::Application;
+use crate::app::filters::Matcher;
+use crate::app::picture::Picture;
+use crate::app::progress::Cancel;
+use crate::error::{ErrorKind, TransformationError};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Time a file must stay untouched, with the same size, before it is considered fully written.
+const DEBOUNCE: Duration = Duration::from_millis(1000);
+const TICK: Duration = Duration::from_millis(250);
+/// Events on a written output are ignored for this long, so writing next to the source does not loop.
+const OUTPUT_GRACE: Duration = Duration::from_secs(10);
+
+pub enum WatchEvent {
+    Processed(Picture),
+    Failed(Picture, TransformationError),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WatchEntry {
+    pub name: String,
+    pub message: String,
+    pub is_error: bool,
+}
+
+impl From<&WatchEvent> for WatchEntry {
+    fn from(event: &WatchEvent) -> Self {
+        match event {
+            WatchEvent::Processed(picture) => Self { name: picture.get_name(), message: "Processed".to_string(), is_error: false },
+            WatchEvent::Failed(picture, err) => Self { name: picture.get_name(), message: err.to_string(), is_error: true },
+        }
+    }
+}
+
+/// Background watch of the source folder, stopped when dropped.
+pub struct Watch {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+struct Settings {
+    app: Application,
+    matcher: Matcher,
+}
+
+struct Pending {
+    last_event: Instant,
+    size: u64,
+}
+
+impl Watch {
+    /// Starts watching `app.paths.source` with the settings of `app` as they are now.
+    /// They are copied once, without the pictures and so without their overrides:
+    /// changing them afterwards takes stopping and starting the watch again.
+    pub fn start<F>(app: &Application, on_event: F) -> notify::Result<Self>
+    where
+        F: Fn(WatchEvent) + Send + 'static,
+    {
+        let mut settings = Settings {
+            app: Application { pictures: Vec::new(), watch_log: Vec::new(), ..app.clone() },
+            matcher: app.filters.matcher().map_err(|e| notify::Error::generic(&e.to_string()))?,
+        };
+        // Events come with absolute paths, the folders have to be absolute too to be compared with them.
+        let paths = &mut settings.app.paths;
+        paths.source = paths.source.canonicalize().map_err(notify::Error::io)?;
+        paths.destination = paths.destination.canonicalize().map_err(notify::Error::io)?;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mode = if settings.app.scan.is_recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&settings.app.paths.source, mode)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let is_stopped = stop.clone();
+        let handle = std::thread::spawn(move || {
+            // The watcher stops sending events once dropped, keep it alive with the thread.
+            let _watcher = watcher;
+            let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+            let mut outputs: HashMap<PathBuf, Instant> = HashMap::new();
+
+            while !is_stopped.load(Ordering::Relaxed) {
+                match receiver.recv_timeout(TICK) {
+                    Ok(Ok(event)) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                        for path in event.paths {
+                            if settings.is_watched(&path) && !outputs.contains_key(&path) {
+                                let size = file_size(&path);
+                                pending.insert(path, Pending { last_event: Instant::now(), size });
+                            }
+                        }
+                    }
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                outputs.retain(|_, written| written.elapsed() < OUTPUT_GRACE);
+
+                let ready: Vec<PathBuf> = pending
+                    .iter_mut()
+                    .filter(|(_, file)| file.last_event.elapsed() >= DEBOUNCE)
+                    .filter_map(|(path, file)| {
+                        let size = file_size(path);
+                        if size == file.size && size > 0 {
+                            return Some(path.clone());
+                        }
+                        // Still being written, wait for another quiet period.
+                        file.size = size;
+                        file.last_event = Instant::now();
+                        None
+                    })
+                    .collect();
+
+                for path in ready {
+                    pending.remove(&path);
+                    if !path.is_file() {
+                        continue;
+                    }
+
+                    let picture = match Picture::new(path.clone(), &settings.app.paths.source) {
+                        Ok(picture) => picture,
+                        // Removed or renamed while waiting, a renamed file comes back with an event of its own.
+                        Err(err) if matches!(&err.kind, ErrorKind::Io(io) if io.kind() == std::io::ErrorKind::NotFound) => continue,
+                        Err(err) => {
+                            on_event(WatchEvent::Failed(Picture { path, ..Picture::default() }, err));
+                            continue;
+                        }
+                    };
+                    match settings.app.transform_picture(&picture, &Cancel::default()) {
+                        Ok(written) => {
+                            for output in written {
+                                outputs.insert(output.file, Instant::now());
+                            }
+                            on_event(WatchEvent::Processed(picture));
+                        }
+                        Err(err) => on_event(WatchEvent::Failed(picture, err)),
+                    }
+                }
+            }
+        });
+
+        Ok(Self { stop, handle: Some(handle) })
+    }
+
+    /// Blocks until the watch ends.
+    pub fn join(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Settings {
+    fn is_watched(&self, path: &Path) -> bool {
+        let paths = &self.app.paths;
+        let Ok(relative) = path.strip_prefix(&paths.source) else { return false };
+        let depth = relative.components().count().saturating_sub(1);
+        let is_output = paths.destination != paths.source && path.starts_with(&paths.destination);
+
+        Application::is_image(path)
+            && !is_output
+            && depth <= self.app.scan.depth as usize
+            && !self.app.scan.is_linked(&paths.source, path)
+            && self.matcher.is_match(relative)
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    path.metadata().map(|metadata| metadata.len()).unwrap_or(0)
+}
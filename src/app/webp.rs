@@ -0,0 +1,85 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use webp::{Encoder, PixelLayout, WebPConfig};
+use crate::app::format::{display_size, parse_size};
+use crate::error::{EncoderError, ErrorKind, Stage, TransformationError};
+use std::sync::Arc;
+
+/// Slowest method, the one trying the most encoding strategies.
+pub const MAX_METHOD: u8 = 6;
+/// Passes libwebp takes to get close to the target size.
+const TARGET_PASSES: i32 = 6;
+
+/// Settings of the WebP encoder, besides the quality shared with the other lossy formats.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webp {
+    /// Lossless encoding, the quality setting is ignored and the lossless effort sets how hard it compresses.
+    pub lossless: bool,
+    /// From 0 to 100, how hard the lossless encoder compresses, higher being slower for smaller files.
+    pub lossless_effort: u8,
+    /// From 0 to 100, lower values let the lossless encoder adjust pixels for smaller files, 100 turning it off.
+    pub near_lossless: u8,
+    /// Quality of the alpha channel, compressed on its own, from 0 to 100.
+    pub alpha_quality: u8,
+    /// From 0 to 6, higher methods are slower for smaller files.
+    pub method: u8,
+    /// Sharper and more accurate conversion of the colours to YUV, slower.
+    pub sharp_yuv: bool,
+    /// Size in bytes libwebp aims for by adjusting the quality over a few passes, 0 turning it off.
+    pub target_size: u64,
+}
+
+impl Default for Webp {
+    fn default() -> Self {
+        Self { lossless: false, lossless_effort: 75, near_lossless: 100, alpha_quality: 100, method: 4, sharp_yuv: false, target_size: 0 }
+    }
+}
+
+impl Webp {
+    pub fn set_method(&mut self, value: String) {
+        self.method = value.parse::<u8>().unwrap_or(4).min(MAX_METHOD);
+    }
+
+    /// Sets the target size from a value such as `50 KB`, anything else turning it off.
+    pub fn set_target_size(&mut self, value: String) {
+        self.target_size = parse_size(&value).unwrap_or(0);
+    }
+
+    pub fn get_target_size(&self) -> String {
+        if self.target_size == 0 {
+            return String::new();
+        }
+        display_size(self.target_size)
+    }
+
+    pub fn encode(&self, image: &DynamicImage, quality: u8) -> Result<Vec<u8>, TransformationError> {
+        let (raw, layout) = if image.color().has_alpha() {
+            (image.to_rgba8().into_raw(), PixelLayout::Rgba)
+        } else {
+            (image.to_rgb8().into_raw(), PixelLayout::Rgb)
+        };
+
+        let mut config = WebPConfig::new().map_err(|_| webp_error("libwebp could not be configured".to_string()))?;
+        config.lossless = self.lossless as i32;
+        // libwebp reads the quality of a lossless encode as its effort.
+        config.quality = if self.lossless { self.lossless_effort } else { quality }.min(100) as f32;
+        config.method = self.method.min(MAX_METHOD) as i32;
+        config.near_lossless = self.near_lossless.min(100) as i32;
+        config.alpha_quality = self.alpha_quality.min(100) as i32;
+        config.use_sharp_yuv = self.sharp_yuv as i32;
+        if self.target_size > 0 && !self.lossless {
+            config.target_size = self.target_size.min(i32::MAX as u64) as i32;
+            config.pass = TARGET_PASSES;
+        }
+
+        let webp = Encoder::new(&raw, layout, image.width(), image.height())
+            .encode_advanced(&config)
+            .map_err(|err| webp_error(format!("{:?}", err)))?;
+        Ok(webp.to_vec())
+    }
+}
+
+fn webp_error(message: String) -> TransformationError {
+    TransformationError::new(Stage::Format, ErrorKind::Webp(Arc::new(EncoderError(message))))
+}
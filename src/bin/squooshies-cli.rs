@@ -0,0 +1,337 @@
+use picturust_dx::app::{Application, Cancel, Preset, Progress, Quality, Speed, Status, Symlinks, Target, Watch, WatchEntry};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const FORMATS: [&str; 6] = ["png", "png-palette", "jpg", "webp", "avif", "tiff"];
+const RESIZE_TYPES: [&str; 3] = ["exact", "fill", "thumbnail"];
+const RESIZE_METHODS: [&str; 5] = ["lanczos3", "nearest", "catmullrom", "triangle", "gaussian"];
+
+const USAGE: &str = "Usage: squooshies-cli --source <DIR> --destination <DIR> [OPTIONS]
+       squooshies-cli --source <DIR> --dry-run [OPTIONS]
+
+Options:
+  -s, --source <DIR>        Directory containing the pictures to transform
+  -d, --destination <DIR>   Directory where the transformed pictures are written
+  -r, --recursive           Include the pictures of the sub folders, mirrored in the destination
+      --depth <LEVELS>      Maximum number of sub folder levels scanned (default: 8)
+      --follow-symlinks     Follow symbolic links while scanning
+      --include <GLOB>      Only pick up the files matching the pattern, can be repeated
+      --exclude <GLOB>      Skip the files matching the pattern, can be repeated
+  -p, --preset <NAME>       Apply a saved preset, options given after it override it
+      --preset-file <FILE>  Apply a preset file, options given after it override it
+      --resize <TYPE>       exact, fill or thumbnail (default: no resizing)
+      --method <METHOD>     lanczos3, nearest, catmullrom, triangle or gaussian
+      --width <PX>          Width used by the resize (default: 250)
+      --height <PX>         Height used by the resize (default: 250)
+      --format <FORMAT>     png, png-palette, jpg, webp, avif or tiff (default: no reformating)
+      --quality <0-100>     Quality used by the jpg, webp and avif encoders, SSIM in percent png-palette aims for (default: 75)
+      --max-size <SIZE>     Highest quality fitting each output under a size such as 200KB
+      --min-quality <0-100> Lowest quality tried by --max-size before downscaling (default: 30)
+      --no-downscale        Fail the pictures --max-size cannot fit instead of downscaling them
+      --target-ssim <0-1>   Lowest quality whose output reaches an SSIM such as 0.98, instead of --quality, avif excepted
+      --speed <1-10>        Speed used by the avif encoder (default: 7)
+      --baseline            Write baseline jpg instead of progressive ones
+      --subsampling <MODE>  Chroma subsampling of the jpg encoder, 444, 422 or 420 (default: 420)
+      --no-optimize-coding  Use the standard Huffman tables of the jpg encoder
+      --no-trellis          Turn off the trellis quantization of the jpg encoder, faster but larger
+      --png-effort <0-6>    Effort of the lossless png optimisation (default: 2)
+      --no-png-optimize     Write png as encoded, without the lossless optimisation
+      --no-png-reduce       Keep the bit depth and colour type of the png outputs
+      --keep-metadata       Keep the metadata chunks of the png outputs
+      --zopfli              Compress png with Zopfli, smaller but much slower
+      --no-dither           Map png-palette pixels to their nearest colour without dithering
+      --palette-min-quality <0-100>
+                            Lowest SSIM, in percent, of a png-palette output, the others keep all their colours
+      --webp-lossless       Encode webp losslessly, ignoring --quality
+      --lossless-effort <0-100>
+                            Effort of the lossless webp encoder, higher is slower and smaller (default: 75)
+      --near-lossless <0-100>
+                            Let the lossless webp encoder adjust pixels, lower is smaller (default: 100, off)
+      --alpha-quality <0-100>
+                            Quality of the webp alpha channel (default: 100)
+      --webp-method <0-6>   Effort of the webp encoder, higher is slower and smaller (default: 4)
+      --sharp-yuv           Use the sharper and slower RGB to YUV conversion of the webp encoder
+      --webp-target-size <SIZE>
+                            Size such as 50KB the webp encoder aims for by adjusting its quality
+      --avif-alpha-quality <1-100>
+                            Quality of the avif alpha channel (default: 80)
+      --alpha-mode <MODE>   Colours of the transparent avif pixels, clean, dirty or premultiplied (default: clean)
+      --color-model <MODEL> Colour model of the avif encoder, ycbcr or rgb (default: ycbcr)
+      --eight-bit           Write 8-bit avif instead of 10-bit ones
+      --avif-threads <N>    Threads of each avif encode (default: 0, all of them)
+      --rotate <ANGLE>      90, 180 or 270
+      --widths <LIST>       Write a variant per width, such as 480,960,1920, instead of resizing
+      --formats <LIST>      Write a variant per format, such as avif,webp,jpg, instead of --format
+  -m, --manifest            Write manifest.json and snippets.html listing every written file
+  -w, --watch               Keep running and transform the pictures added to the source
+  -n, --dry-run             Encode in memory and report the projected sizes without writing anything
+  -h, --help                Print this message";
+
+enum Mode {
+    Transform,
+    Watch,
+    DryRun,
+}
+
+fn main() -> ExitCode {
+    let (app, mode) = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match mode {
+        Mode::Transform => run(app),
+        Mode::Watch => watch(app),
+        Mode::DryRun => dry_run(app),
+    }
+}
+
+fn watch(app: Application) -> ExitCode {
+    let started = Watch::start(&app, |event| {
+        let entry = WatchEntry::from(&event);
+        if entry.is_error {
+            eprintln!("{} : {}", entry.name, entry.message);
+        } else {
+            println!("{} : {}", entry.name, entry.message);
+        }
+    });
+
+    match started {
+        Ok(watch) => {
+            println!("Watching {}", app.paths.source.display());
+            watch.join();
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Could not watch {} : {}", app.paths.source.display(), err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut app: Application) -> ExitCode {
+    let selected = app.pictures.len();
+    println!("Transforming {} pictures from {}", selected, app.paths.source.display());
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Could not start the runtime : {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let finished = AtomicUsize::new(0);
+    let on_progress = |progress: Progress| {
+        let name = progress.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        match progress.status {
+            Status::Done | Status::Failed(_) | Status::Skipped => {
+                let count = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                let qualities: Vec<String> = progress
+                    .outputs
+                    .iter()
+                    .map(|output| (output, output.get_encoding()))
+                    .filter(|(_, encoding)| !encoding.is_empty())
+                    .map(|(output, encoding)| format!("{} at {}", output.path, encoding))
+                    .collect();
+                if qualities.is_empty() {
+                    println!("[{}/{}] {} : {}", count, selected, name, progress.status.get_label());
+                } else {
+                    println!("[{}/{}] {} : {} ({})", count, selected, name, progress.status.get_label(), qualities.join(", "));
+                }
+            }
+            _ => {}
+        }
+    };
+
+    if let Err(err) = runtime.block_on(app.transform_with_progress(&Cancel::default(), on_progress)) {
+        eprintln!("Could not transform pictures : {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    for error in &app.errors {
+        eprintln!("{}", error);
+    }
+    println!("{} transformed, {} failed", selected - app.errors.len(), app.errors.len());
+
+    if app.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn dry_run(app: Application) -> ExitCode {
+    println!("Estimating {} pictures from {}", app.pictures.len(), app.paths.source.display());
+    let estimate = app.estimate();
+
+    for picture in &estimate.pictures {
+        println!("{} : {}", picture.name, picture.get_summary());
+    }
+    for failure in &estimate.failures {
+        eprintln!("{}", failure);
+    }
+    println!("Total : {}", estimate.get_summary());
+
+    if estimate.failures.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<(Application, Mode)>, String> {
+    let mut app = Application::default();
+    let mut mode = Mode::Transform;
+    let mut min_quality = None;
+    let mut downscale = true;
+    let mut source = None;
+    let mut destination = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "-s" | "--source" => source = Some(PathBuf::from(value()?)),
+            "-d" | "--destination" => destination = Some(PathBuf::from(value()?)),
+            "-r" | "--recursive" => app.scan.is_recursive = true,
+            "--depth" => app.scan.depth = parse_number(&arg, value()?)?,
+            "--follow-symlinks" => app.scan.symlinks = Symlinks::Follow,
+            "--include" => app.filters.patterns.push(value()?),
+            "--exclude" => app.filters.patterns.push(format!("!{}", value()?)),
+            "-p" | "--preset" => Preset::find(&value()?).map_err(|e| e.to_string())?.apply(&mut app),
+            "--preset-file" => Preset::read(Path::new(&value()?)).map_err(|e| e.to_string())?.apply(&mut app),
+            "--resize" => app.resize.set_resize_type(parse_choice(&arg, value()?, &RESIZE_TYPES)?),
+            "--method" => app.resize.set_method(parse_choice(&arg, value()?, &RESIZE_METHODS)?),
+            "--width" => app.resize.width = parse_number(&arg, value()?)?,
+            "--height" => app.resize.height = parse_number(&arg, value()?)?,
+            "--format" => app.format.set_format(parse_choice(&arg, value()?, &FORMATS)?),
+            "--quality" => app.format.quality = Quality { value: parse_range(&arg, value()?, 0..=100)? },
+            "--speed" => app.format.speed = Speed { value: parse_range(&arg, value()?, 1..=10)? },
+            "--baseline" => app.format.jpeg.progressive = false,
+            "--subsampling" => app.format.jpeg.set_subsampling(parse_choice(&arg, value()?, &["444", "422", "420"])?),
+            "--no-optimize-coding" => app.format.jpeg.optimize_coding = false,
+            "--no-trellis" => app.format.jpeg.trellis = false,
+            "--png-effort" => app.format.png.effort = parse_number(&arg, value()?)?.min(6) as u8,
+            "--no-png-optimize" => app.format.png.optimize = false,
+            "--no-png-reduce" => app.format.png.reduce = false,
+            "--keep-metadata" => app.format.png.strip = false,
+            "--zopfli" => app.format.png.zopfli = true,
+            "--no-dither" => app.format.png.dithering = false,
+            "--palette-min-quality" => app.format.png.min_quality = parse_number(&arg, value()?)?.min(100) as u8,
+            "--webp-lossless" => app.format.webp.lossless = true,
+            "--lossless-effort" => app.format.webp.lossless_effort = parse_number(&arg, value()?)?.min(100) as u8,
+            "--near-lossless" => app.format.webp.near_lossless = parse_number(&arg, value()?)?.min(100) as u8,
+            "--alpha-quality" => app.format.webp.alpha_quality = parse_number(&arg, value()?)?.min(100) as u8,
+            "--webp-method" => app.format.webp.method = parse_number(&arg, value()?)?.min(6) as u8,
+            "--sharp-yuv" => app.format.webp.sharp_yuv = true,
+            "--webp-target-size" => {
+                let size = value()?;
+                app.format.webp.set_target_size(size.clone());
+                if app.format.webp.target_size == 0 {
+                    return Err(format!("Invalid value for {} : {}", arg, size));
+                }
+            }
+            "--avif-alpha-quality" => app.format.avif.alpha_quality = parse_number(&arg, value()?)?.clamp(1, 100) as u8,
+            "--alpha-mode" => app.format.avif.set_alpha_mode(parse_choice(&arg, value()?, &["clean", "dirty", "premultiplied"])?),
+            "--color-model" => app.format.avif.set_color_model(parse_choice(&arg, value()?, &["ycbcr", "rgb"])?),
+            "--eight-bit" => app.format.avif.ten_bit = false,
+            "--avif-threads" => app.format.avif.threads = parse_number(&arg, value()?)?.min(u8::MAX as u32) as u8,
+            "--max-size" => {
+                let size = value()?;
+                app.format.set_max_size(size.clone());
+                if app.format.max_size.is_none() {
+                    return Err(format!("Invalid value for {} : {}", arg, size));
+                }
+            }
+            "--target-ssim" => {
+                let ssim = value()?;
+                match ssim.parse::<f64>() {
+                    Ok(score) if (0.0..=1.0).contains(&score) => app.format.target = Some(Target { ssim: score }),
+                    _ => return Err(format!("Invalid value for {} : {}", arg, ssim)),
+                }
+            }
+            "--min-quality" => min_quality = Some(parse_number(&arg, value()?)?.min(100) as u8),
+            "--no-downscale" => downscale = false,
+            "--rotate" => app.rotate.set_angle(parse_choice(&arg, value()?, &["90", "180", "270"])?),
+            "--widths" => app.variants.set_widths(value()?),
+            "--formats" => {
+                let formats = value()?;
+                for format in formats.split(',').map(str::trim).filter(|format| !format.is_empty()) {
+                    parse_choice(&arg, format.to_string(), &FORMATS)?;
+                }
+                app.variants.set_formats(formats);
+            }
+            "-m" | "--manifest" => app.write_manifest = true,
+            "-w" | "--watch" => mode = Mode::Watch,
+            "-n" | "--dry-run" => mode = Mode::DryRun,
+            _ => return Err(format!("Unknown argument : {}", arg)),
+        }
+    }
+
+    match app.format.max_size.as_mut() {
+        Some(max_size) => {
+            max_size.min_quality = min_quality.unwrap_or(max_size.min_quality);
+            max_size.downscale = downscale;
+        }
+        None if min_quality.is_some() || !downscale => return Err("--min-quality and --no-downscale need --max-size".to_string()),
+        None => {}
+    }
+
+    let source = source.ok_or("Missing --source")?;
+    if !source.is_dir() {
+        return Err(format!("Source is not a directory : {}", source.display()));
+    }
+    // A dry run writes nothing, the destination only keeps its own outputs out of the scan.
+    let destination = match (destination, &mode) {
+        (Some(destination), _) => destination,
+        (None, Mode::DryRun) => source.clone(),
+        (None, _) => return Err("Missing --destination".to_string()),
+    };
+    if !destination.is_dir() {
+        return Err(format!("Destination is not a directory : {}", destination.display()));
+    }
+
+    if let Err(err) = app.filters.matcher() {
+        return Err(format!("Invalid filter : {}", err));
+    }
+
+    app.set_destination_path(Some(destination));
+    app.set_source_path(Some(source));
+
+    Ok(Some((app, mode)))
+}
+
+/// `value` when it is one of `choices`, the setters of the application falling back to a default on anything else.
+fn parse_choice(arg: &str, value: String, choices: &[&str]) -> Result<String, String> {
+    if !choices.contains(&value.as_str()) {
+        return Err(format!("Invalid value for {} : {}, expected {}", arg, value, choices.join(", ")));
+    }
+    Ok(value)
+}
+
+fn parse_number(arg: &str, value: String) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid value for {} : {}", arg, value))
+}
+
+/// `value` when it is a number within `range`, rather than clamped like the optional settings.
+fn parse_range(arg: &str, value: String, range: RangeInclusive<u8>) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(number) if range.contains(&number) => Ok(number),
+        _ => Err(format!("Invalid value for {} : {}, expected {} to {}", arg, value, range.start(), range.end())),
+    }
+}
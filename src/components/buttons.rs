@@ -14,6 +14,45 @@ pub fn TransformButton(on_click: EventHandler<()>, is_disabled: bool) -> Element
     }
 }
 
+#[component]
+pub fn WatchButton(on_click: EventHandler<()>, is_watching: bool, is_disabled: bool) -> Element {
+    rsx! {
+        button {
+            class: "w-full p-4 mb-8 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700 disabled:bg-slate-800 disabled:text-slate-400",
+            onclick: move |_| on_click(()),
+            disabled: is_disabled,
+            title: "The watch uses the settings as they are when it starts, restart it after changing them",
+            if is_watching { "Stop watching" } else { "Watch source folder" }
+        }
+    }
+}
+
+#[component]
+pub fn SettingButton(label: &'static str, on_click: EventHandler<()>) -> Element {
+    rsx! {
+        button {
+            class: "w-full p-4 mb-8 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+            onclick: move |_| on_click(()),
+            {label}
+        }
+    }
+}
+
+#[component]
+pub fn ToggleButton(label: &'static str, is_active: bool, on_click: EventHandler<()>) -> Element {
+    rsx! {
+        button {
+            class: if is_active {
+                "p-4 rounded-lg bg-blue-500 text-slate-200"
+            } else {
+                "p-4 rounded-lg bg-slate-800 text-slate-400 hover:bg-slate-700"
+            },
+            onclick: move |_| on_click(()),
+            {label}
+        }
+    }
+}
+
 #[component]
 pub fn OrderByButton(is_asc: bool, on_click: EventHandler<()>) -> Element {
     rsx! {
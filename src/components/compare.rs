@@ -0,0 +1,135 @@
+use std::rc::Rc;
+use dioxus::html::geometry::PixelsRect;
+use dioxus::prelude::*;
+use crate::app::Comparison;
+
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Drag {
+    None,
+    Split,
+    Pan { x: f64, y: f64 },
+}
+
+/// Full-window view of a picture over its output, the split line showing the original on its left.
+/// Both sides share the zoom and the pan: scrolling zooms, dragging outside of the line pans.
+#[component]
+pub fn CompareView(comparison: Comparison, on_close: EventHandler<()>) -> Element {
+    let mut split = use_signal(|| 50.0);
+    let mut zoom = use_signal(|| MIN_ZOOM);
+    let mut offset = use_signal(|| (0.0, 0.0));
+    let mut drag = use_signal(|| Drag::None);
+    let mut viewer = use_signal(|| None::<Rc<MountedData>>);
+    let mut bounds = use_signal(|| None::<PixelsRect>);
+
+    // The window may have been resized since the viewer was mounted.
+    let mut measure = move || {
+        if let Some(viewer) = viewer() {
+            spawn(async move {
+                if let Ok(rect) = viewer.get_client_rect().await {
+                    bounds.set(Some(rect));
+                }
+            });
+        }
+    };
+
+    let (x, y) = offset();
+    let transform = format!("transform: translate({}px, {}px) scale({});", x, y, zoom());
+
+    rsx! {
+        div { class: "fixed inset-0 z-50 bg-slate-950 flex flex-col",
+            header { class: "w-full py-4 px-8 flex flex-row justify-between items-center gap-8",
+                label { class: "text-slate-200", {comparison.name.clone()} }
+                div { class: "flex flex-row items-center gap-2",
+                    span { class: "text-sm text-slate-400", {format!("{:.0}%", zoom() * 100.0)} }
+                    button {
+                        class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                        onclick: move |_| {
+                            zoom.set(MIN_ZOOM);
+                            offset.set((0.0, 0.0));
+                            split.set(50.0);
+                        },
+                        "Reset"
+                    }
+                    button {
+                        class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+            div {
+                class: "relative flex-1 overflow-hidden select-none cursor-grab",
+                onmounted: move |evt| {
+                    viewer.set(Some(evt.data()));
+                    measure();
+                },
+                onmousedown: move |evt| {
+                    let point = evt.client_coordinates();
+                    drag.set(Drag::Pan { x: point.x, y: point.y });
+                },
+                onmousemove: move |evt| {
+                    let point = evt.client_coordinates();
+                    match drag() {
+                        Drag::Split => {
+                            if let Some(rect) = bounds() {
+                                let position = (point.x - rect.origin.x) / rect.size.width * 100.0;
+                                split.set(position.clamp(0.0, 100.0));
+                            }
+                        }
+                        Drag::Pan { x, y } => {
+                            let (left, top) = offset();
+                            offset.set((left + point.x - x, top + point.y - y));
+                            drag.set(Drag::Pan { x: point.x, y: point.y });
+                        }
+                        Drag::None => {}
+                    }
+                },
+                onmouseup: move |_| drag.set(Drag::None),
+                onmouseleave: move |_| drag.set(Drag::None),
+                onwheel: move |evt| {
+                    let factor = if evt.delta().strip_units().y < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+                    let value = (zoom() * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+                    zoom.set(value);
+                    if value == MIN_ZOOM {
+                        offset.set((0.0, 0.0));
+                    }
+                },
+                div { class: "absolute inset-0",
+                    img {
+                        src: comparison.original.source.clone(),
+                        draggable: false,
+                        class: "w-full h-full object-contain pointer-events-none",
+                        style: transform.clone()
+                    }
+                }
+                div { class: "absolute inset-0", style: format!("clip-path: inset(0 0 0 {}%);", split()),
+                    img {
+                        src: comparison.encoded.source.clone(),
+                        draggable: false,
+                        class: "w-full h-full object-contain pointer-events-none",
+                        style: transform
+                    }
+                }
+                div {
+                    class: "absolute top-0 h-full w-1 -translate-x-2/4 bg-slate-200 cursor-col-resize",
+                    style: format!("left: {}%;", split()),
+                    onmousedown: move |evt| {
+                        evt.stop_propagation();
+                        measure();
+                        drag.set(Drag::Split);
+                    }
+                }
+                span { class: "absolute bottom-2 left-2 px-2 py-1 rounded-lg bg-slate-800 text-sm text-slate-200",
+                    {comparison.original.get_summary()}
+                }
+                span { class: "absolute bottom-2 right-2 px-2 py-1 rounded-lg bg-slate-800 text-sm text-slate-200",
+                    {comparison.encoded.get_summary()}
+                }
+            }
+        }
+    }
+}
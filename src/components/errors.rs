@@ -0,0 +1,49 @@
+use dioxus::prelude::*;
+use crate::app::Failure;
+use crate::error::TransformationError;
+
+/// Pictures the last batch failed on, which can be retried, and the manifest error apart since there is no picture to retry for it.
+#[component]
+pub fn ErrorPanel(errors: Vec<Failure>, manifest_error: Option<TransformationError>, on_retry: EventHandler<()>, on_dismiss: EventHandler<()>) -> Element {
+    let has_failed = !errors.is_empty();
+    rsx! {
+        div { class: "mx-8 p-4 rounded-lg bg-gray-900 flex flex-col gap-2",
+            div { class: "w-full flex flex-row justify-between items-center gap-2",
+                if has_failed {
+                    label { class: "text-red-500", {format!("{} pictures failed", errors.len())} }
+                }
+                if !has_failed {
+                    label { class: "text-red-500", "The manifest could not be written" }
+                }
+                div { class: "flex flex-row gap-2",
+                    if has_failed {
+                        button {
+                            class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                            onclick: move |_| on_retry.call(()),
+                            "Retry failed"
+                        }
+                    }
+                    button {
+                        class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                        onclick: move |_| on_dismiss.call(()),
+                        "Dismiss"
+                    }
+                }
+            }
+            if let Some(error) = manifest_error {
+                p { class: "text-sm text-slate-400",
+                    span { class: "text-slate-200", "manifest.json" }
+                    {format!(" · {} · {}", error.get_stage(), error.kind)}
+                }
+            }
+            ul { class: "w-full flex flex-col gap-2 text-sm",
+                for failure in errors.into_iter() {
+                    li { class: "text-slate-400",
+                        span { class: "text-slate-200", {failure.name.clone()} }
+                        {format!(" · {} · {}", failure.error.get_stage(), failure.error.kind)}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use dioxus::prelude::*;
+use crate::app::Estimate;
+
+#[component]
+pub fn EstimatePanel(estimate: Estimate, on_dismiss: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "mx-8 p-4 rounded-lg bg-gray-900 flex flex-col gap-2",
+            div { class: "w-full flex flex-row justify-between items-center gap-2",
+                label { class: "text-slate-200", {format!("Estimate : {}", estimate.get_summary())} }
+                button {
+                    class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                    onclick: move |_| on_dismiss.call(()),
+                    "Dismiss"
+                }
+            }
+            ul { class: "w-full flex flex-col gap-2 text-sm",
+                for picture in estimate.pictures.iter() {
+                    li { class: "text-slate-400",
+                        span { class: "text-slate-200", {picture.name.clone()} }
+                        {format!(" · {}", picture.get_summary())}
+                    }
+                }
+                for failure in estimate.failures.iter() {
+                    li { class: "text-red-500",
+                        span { class: "text-slate-200", {failure.name.clone()} }
+                        {format!(" · {} · {}", failure.error.get_stage(), failure.error.kind)}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,9 +2,17 @@ mod selects;
 mod traits;
 mod file_selector;
 mod numbers;
+mod texts;
 mod buttons;
 mod checkbox;
 mod pictures;
+mod watch_log;
+mod variants;
+mod progress;
+mod errors;
+mod estimate;
+mod compare;
+mod preview;
 
 pub use checkbox::Checkbox;
 pub use selects::Selectable;
@@ -12,6 +20,17 @@ pub use selects::SelectableSetting;
 pub use traits::{ToHtml, Sort};
 pub use file_selector::FileSelector;
 pub use numbers::Numbers;
+pub use texts::Texts;
 pub use buttons::OrderByButton;
 pub use buttons::TransformButton;
-pub use pictures::Pictures;
\ No newline at end of file
+pub use buttons::SettingButton;
+pub use buttons::ToggleButton;
+pub use buttons::WatchButton;
+pub use pictures::{Pictures, Viewport};
+pub use watch_log::WatchLog;
+pub use variants::FormatToggles;
+pub use progress::ProgressBar;
+pub use errors::ErrorPanel;
+pub use estimate::EstimatePanel;
+pub use compare::CompareView;
+pub use preview::PreviewPane;
\ No newline at end of file
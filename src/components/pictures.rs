@@ -1,35 +1,177 @@
+use std::rc::Rc;
 use dioxus::prelude::*;
-use crate::app::{Application, Picture};
-use crate::components::Checkbox;
+use crate::app::{format_weight, Application, Picture, Quality, ResizeType, Status};
+use crate::components::{Checkbox, Numbers, SelectableSetting, SettingButton};
 
+/// Width of a card, `w-80`, until the first card is measured.
+const CARD_WIDTH: f64 = 320.0;
+/// Height given to every card, the outputs and settings below the thumbnail scroll inside it so the rows keep their place.
+/// The rows are laid out from the measured size of the cards, so a zoomed or restyled card does not shift them.
+const CARD_HEIGHT: f64 = 712.0;
+/// Gap between the cards, `gap-16`.
+const GAP: f64 = 64.0;
+/// Padding around the grid, the rows out of view are added to it.
+const PADDING: f64 = 32.0;
+/// Rows rendered above and below the visible ones, so scrolling does not show empty space before the next measure.
+const BUFFER: usize = 2;
+
+/// Height of the scrolled section the grid is in, measured again on every scroll.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Viewport {
+    pub height: f64,
+    pub scrolled: usize,
+}
+
+/// Rows of the grid the viewport shows, the others are replaced by padding of the same height.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Rows {
+    columns: usize,
+    first_row: usize,
+    last_row: usize,
+}
+
+/// Grid of the pictures, only the rows in view are rendered so folders of any size stay responsive.
+/// It reads the application through memos, the settings and the progress of a batch changing do not render it again.
+/// The pictures of those rows still without thumbnail are passed to `on_missing_thumbnails`.
 #[component]
-pub fn Pictures(app: Signal<Application>) -> Element {
-    
+pub fn Pictures(app: Signal<Application>, viewport: ReadOnlySignal<Viewport>, on_compare: EventHandler<usize>, on_missing_thumbnails: EventHandler<Vec<Picture>>) -> Element {
+    let mut grid = use_signal(|| None::<Rc<MountedData>>);
+    let mut visible = use_signal(|| Rows { columns: 1, first_row: 0, last_row: BUFFER });
+    let mut card = use_signal(|| (CARD_WIDTH, CARD_HEIGHT));
+
+    use_effect(move || {
+        let viewport = viewport();
+        let card = card();
+        if let Some(grid) = grid() {
+            spawn(async move {
+                if let Ok(rect) = grid.get_client_rect().await {
+                    visible.set(Rows::new(rect.size.width, -rect.origin.y, viewport.height, card));
+                }
+            });
+        }
+    });
+
+    // Only the pictures in view are cloned out of the application, keyed by path so each card keeps its own state while scrolling.
+    let shown = use_memo(move || {
+        let Rows { columns, first_row, last_row } = visible();
+        let (_, card_height) = card();
+        app.with(|a| {
+            let total = a.pictures.len();
+            let rows = total.div_ceil(columns);
+            let (first_row, last_row) = (first_row.min(rows), last_row.min(rows));
+            let start = first_row * columns;
+            let pictures: Vec<(String, usize, bool, Picture)> = a.pictures[start..(last_row * columns).min(total)]
+                .iter()
+                .enumerate()
+                .map(|(offset, picture)| {
+                    let is_focused = a.focused.as_ref() == Some(&picture.path);
+                    (picture.path.display().to_string(), start + offset, is_focused, picture.clone())
+                })
+                .collect();
+            let style = format!(
+                "padding-top: {}px; padding-bottom: {}px;",
+                PADDING + first_row as f64 * (card_height + GAP),
+                PADDING + (rows - last_row) as f64 * (card_height + GAP),
+            );
+            (style, pictures)
+        })
+    });
+    use_effect(move || {
+        let missing: Vec<Picture> = shown.with(|(_, pictures)| {
+            pictures
+                .iter()
+                .filter(|(_, _, _, picture)| picture.thumbnail.is_none())
+                .map(|(_, _, _, picture)| picture.clone())
+                .collect()
+        });
+        if !missing.is_empty() {
+            on_missing_thumbnails.call(missing);
+        }
+    });
+    let (style, pictures) = shown();
+
     rsx!{
-        for (index , picture) in app().pictures.into_iter().enumerate() {
-            Picture { picture, index, app }
+        div {
+            class: "w-full h-fit px-8 m-0 flex flex-row flex-wrap gap-16",
+            style,
+            onmounted: move |evt| grid.set(Some(evt.data())),
+            for (key, index, is_focused, picture) in pictures {
+                Picture {
+                    key: "{key}",
+                    is_focused,
+                    picture,
+                    index,
+                    app,
+                    on_compare,
+                    on_measure: move |size| {
+                        if *card.peek() != size {
+                            card.set(size);
+                        }
+                    }
+                }
+            }
         }
     }
 }
 
+impl Rows {
+    /// Rows between `top` and `top + height`, both relative to the top of the grid, for cards of the `card` width and height.
+    fn new(width: f64, top: f64, height: f64, (card_width, card_height): (f64, f64)) -> Self {
+        let columns = ((width - 2.0 * PADDING + GAP) / (card_width + GAP)).floor().max(1.0) as usize;
+        let first_row = ((top - PADDING) / (card_height + GAP)).floor().max(0.0) as usize;
+        let last_row = ((top + height - PADDING) / (card_height + GAP)).ceil().max(0.0) as usize;
+        Self { columns, first_row: first_row.saturating_sub(BUFFER), last_row: last_row + BUFFER }
+    }
+}
+
 
+/// A card of the grid, reporting its rendered size through `on_measure` once mounted.
 #[component]
-fn Picture(picture: Picture, index: usize, app: Signal<Application>) -> Element {
-    
+fn Picture(picture: Picture, index: usize, is_focused: bool, app: Signal<Application>, on_compare: EventHandler<usize>, on_measure: EventHandler<(f64, f64)>) -> Element {
+    let mut is_editing = use_signal(|| false);
+    let settings_label = if is_editing() { "Hide settings" } else { "Picture settings" };
+    let style = format!("height: {}px;", CARD_HEIGHT);
+
     rsx! {
-        figure { key: "{picture.path.to_str().unwrap_or_default()}", class: "w-80 h-full flex flex-col gap-8",
+        figure {
+            class: "w-80 flex flex-col gap-8",
+            style,
+            onmounted: move |evt| {
+                let card = evt.data();
+                spawn(async move {
+                    if let Ok(rect) = card.get_client_rect().await {
+                        on_measure.call((rect.size.width, rect.size.height));
+                    }
+                });
+            },
             div {
-                class: "w-full h-full shadow-2xl drop-shadow-2xl shadow-slate-600 relative",
+                class: "w-full h-80 shrink-0 shadow-2xl drop-shadow-2xl shadow-slate-600 relative",
                 onclick: move |_| {
                     let state = !app.with(|a| a.pictures[index].is_selected);
                     app.with_mut(|a| a.pictures[index].is_selected = state);
                 },
-                img {
-                    src: picture.get_path(),
-                    loading: "lazy",
-                    width: "320px",
-                    height: "320px",
-                    class: "w-80 h-80 object-cover object-center rounded-lg"
+                if let Some(thumbnail) = picture.get_thumbnail() {
+                    img {
+                        src: thumbnail,
+                        loading: "lazy",
+                        width: "320px",
+                        height: "320px",
+                        class: "w-80 h-80 object-cover object-center rounded-lg"
+                    }
+                } else {
+                    div { class: "w-80 h-80 rounded-lg bg-slate-800" }
+                }
+                if let Status::Failed(err) = &picture.status {
+                    span {
+                        class: "absolute bottom-2 left-2 px-2 py-1 rounded-lg bg-red-500 text-sm text-slate-200",
+                        title: err.clone(),
+                        "Failed"
+                    }
+                }
+                if !picture.overrides.is_empty() {
+                    span { class: "absolute top-2 left-2 px-2 py-1 rounded-lg bg-blue-500 text-sm text-slate-200",
+                        "Custom"
+                    }
                 }
                 Checkbox {
                     is_checked: picture.is_selected,
@@ -42,10 +184,142 @@ fn Picture(picture: Picture, index: usize, app: Signal<Application>) -> Element
                     }
                 }
             }
-            figcaption { class: "w-full p-4 text-center flex flex-col gap-2",
-                div { class: "w-full text-slate-400", {picture.get_name()} }
-                div { class: "w-full text-slate-500", {picture.get_weight()} }
-                div { class: "w-full text-slate-500", {picture.get_size()} }
+            div { class: "w-full flex-1 min-h-0 overflow-y-auto flex flex-col gap-8",
+                figcaption {
+                    class: "w-full p-4 text-center flex flex-col gap-2 cursor-pointer",
+                    title: "Preview at the current settings",
+                    onclick: move |_| app.with_mut(|a| a.focus(index)),
+                    div {
+                        class: if is_focused { "w-full text-blue-500" } else { "w-full text-slate-400" },
+                        {picture.get_name()}
+                    }
+                    div { class: "w-full text-slate-500", {picture.get_weight()} }
+                    div { class: "w-full text-slate-500", {picture.get_size()} }
+                    for output in picture.outputs.iter() {
+                        div { class: "w-full text-sm text-slate-500",
+                            {match output.get_encoding().as_str() {
+                                "" => format!("{} · {}", output.path, format_weight(output.bytes)),
+                                encoding => format!("{} · {} · {}", output.path, format_weight(output.bytes), encoding),
+                            }}
+                        }
+                    }
+                    if picture.status != Status::Idle {
+                        div {
+                            class: match picture.status {
+                                Status::Done => "w-full text-green-500",
+                                Status::Failed(_) => "w-full text-red-500",
+                                Status::Processing => "w-full text-slate-200",
+                                Status::Skipped => "w-full text-slate-400",
+                                _ => "w-full text-slate-500",
+                            },
+                            title: if let Status::Failed(err) = &picture.status { err.clone() } else { String::new() },
+                            {picture.status.get_label()}
+                        }
+                    }
+                }
+                SettingButton {
+                    label: "Compare with the output",
+                    on_click: move |_| on_compare.call(index)
+                }
+                SettingButton {
+                    label: settings_label,
+                    on_click: move |_| is_editing.set(!is_editing())
+                }
+                if is_editing() {
+                    PictureSettings { picture, index, app }
+                }
+            }
+        }
+    }
+}
+
+/// Settings of a single picture, the batch settings are shown until one of them is changed.
+#[component]
+fn PictureSettings(picture: Picture, index: usize, app: Signal<Application>) -> Element {
+    let batch = use_memo(move || app.with(|a| (a.resize.clone(), a.format.clone(), a.rotate.clone())));
+    let (resize, format, rotate) = batch.with(|(resize, format, rotate)| {
+        (
+            picture.overrides.get_resize(resize).clone(),
+            picture.overrides.get_format(format).clone(),
+            picture.overrides.get_rotate(rotate).clone(),
+        )
+    });
+
+    rsx! {
+        div { class: "w-full flex flex-col",
+            SelectableSetting {
+                options: resize.clone(),
+                label: "Resize",
+                on_change: move |evt| {
+                    app.with_mut(|a| a.resize_override(index).set_resize_type(evt));
+                }
+            }
+            if resize.resize_type.is_some() {
+                if resize.resize_type != Some(ResizeType::Exact) {
+                    SelectableSetting {
+                        options: resize.method.clone(),
+                        label: "Method",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.resize_override(index).set_method(evt));
+                        }
+                    }
+                }
+                Numbers {
+                    value: resize.width,
+                    min: 0,
+                    max: 8192,
+                    step: 1,
+                    label: "Width",
+                    on_change: move |evt: String| {
+                        let value = evt.parse::<u32>().unwrap_or(0);
+                        app.with_mut(|a| a.resize_override(index).width = value);
+                    }
+                }
+                Numbers {
+                    value: resize.height,
+                    min: 0,
+                    max: 8192,
+                    step: 1,
+                    label: "Height",
+                    on_change: move |evt: String| {
+                        let value = evt.parse::<u32>().unwrap_or(0);
+                        app.with_mut(|a| a.resize_override(index).height = value);
+                    }
+                }
+            }
+            SelectableSetting {
+                options: format.clone(),
+                label: "Format",
+                on_change: move |evt| {
+                    app.with_mut(|a| a.format_override(index).set_format(evt));
+                }
+            }
+            if format.is_lossy() {
+                Numbers {
+                    value: format.quality.value,
+                    min: 0,
+                    max: 100,
+                    step: 1,
+                    label: "Quality",
+                    on_change: move |evt: String| {
+                        app.with_mut(|a| a.format_override(index).quality = Quality::from(evt));
+                    }
+                }
+            }
+            SelectableSetting {
+                options: rotate,
+                label: "Rotate",
+                on_change: move |evt| {
+                    app.with_mut(|a| a.rotate_override(index).set_angle(evt));
+                }
+            }
+            if !picture.overrides.is_empty() {
+                SettingButton {
+                    label: "Use batch settings",
+                    on_click: move |_| {
+                        app.with_mut(|a| a.pictures[index].overrides = Default::default());
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,90 @@
+use std::time::Duration;
+use dioxus::prelude::*;
+use crate::app::{Application, Cancel, Failure, Preview};
+
+/// Delay without any change before the focused picture is encoded again.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The focused picture encoded in the background each time the settings change.
+/// A change cancels the encoding still running, it stops before its next step and its result is dropped.
+/// Only the focused picture and its settings are sent to the background task.
+#[component]
+pub fn PreviewPane(app: Signal<Application>) -> Element {
+    let mut preview = use_signal(|| None::<Result<Preview, Failure>>);
+    let mut is_encoding = use_signal(|| false);
+    let mut generation = use_signal(|| 0_usize);
+    let mut cancel = use_signal(Cancel::default);
+    let job = use_memo(move || app.with(|a| a.preview_job()));
+
+    use_effect(move || {
+        let Some(job) = job() else {
+            preview.set(None);
+            return;
+        };
+        let current = {
+            let mut generation = generation.write();
+            *generation += 1;
+            *generation
+        };
+        cancel.peek().cancel();
+        let token = Cancel::default();
+        cancel.set(token.clone());
+
+        spawn(async move {
+            tokio::time::sleep(DEBOUNCE).await;
+            if *generation.peek() != current {
+                return;
+            }
+            is_encoding.set(true);
+            let result = tokio::task::spawn_blocking(move || {
+                Preview::new(&job, &token).map_err(|error| Failure { name: job.get_name(), error })
+            }).await;
+            if *generation.peek() != current {
+                return;
+            }
+            match result {
+                Ok(Err(failure)) if failure.error.is_cancelled() => {}
+                Ok(result) => preview.set(Some(result)),
+                Err(_) => preview.set(None),
+            }
+            is_encoding.set(false);
+        });
+    });
+
+    let status = if is_encoding() { "Encoding..." } else { "" };
+
+    rsx! {
+        div { class: "mx-8 p-4 rounded-lg bg-gray-900 flex flex-col gap-2",
+            div { class: "w-full flex flex-row justify-between items-center gap-2",
+                label { class: "text-slate-200",
+                    {match preview() {
+                        Some(Ok(result)) => format!("Preview : {}", result.name),
+                        Some(Err(failure)) => format!("Preview : {}", failure.name),
+                        None => "Preview".to_string(),
+                    }}
+                }
+                span { class: "text-sm text-slate-400", {status} }
+                button {
+                    class: "px-4 py-2 rounded-lg bg-slate-800 text-slate-200 hover:bg-slate-700",
+                    onclick: move |_| {
+                        cancel.peek().cancel();
+                        app.with_mut(|a| a.focused = None);
+                    },
+                    "Close"
+                }
+            }
+            if let Some(Ok(result)) = preview() {
+                img {
+                    src: result.output.source.clone(),
+                    class: "w-full h-80 object-contain"
+                }
+                div { class: "text-sm text-slate-400", {result.get_summary()} }
+            }
+            if let Some(Err(failure)) = preview() {
+                div { class: "text-sm text-red-500",
+                    {format!("{} · {}", failure.error.get_stage(), failure.error.kind)}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use dioxus::prelude::*;
+use crate::app::BatchProgress;
+
+#[component]
+pub fn ProgressBar(progress: BatchProgress) -> Element {
+    rsx! {
+        div { class: "flex-1 flex flex-col justify-center gap-2",
+            div { class: "w-full text-sm text-slate-400", {progress.get_summary()} }
+            div { class: "w-full h-2 rounded-lg bg-slate-800",
+                div {
+                    class: "h-2 rounded-lg bg-blue-500 transition-all",
+                    style: "width: {progress.get_percent()}%"
+                }
+            }
+        }
+    }
+}
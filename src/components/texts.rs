@@ -0,0 +1,17 @@
+use dioxus::prelude::*;
+
+#[component]
+pub fn Texts(value: String, label: &'static str, placeholder: &'static str, on_change: EventHandler<String>) -> Element {
+    rsx! {
+        label { class: "w-full text-slate-200", {label} }
+        div { class: "w-full text-slate-200 mb-8 mt-4 bg-transparent border rounded-lg border-slate-700 has-[:focus]:border-blue-500 focus:border-blue-500 hover:border-blue-500",
+            input {
+                r#type: "text",
+                class: "w-full p-4 bg-transparent appearance-none text-slate-200 focus:outline-none focus:ring-0 peer",
+                value: "{value}",
+                placeholder: "{placeholder}",
+                onchange: move |evt| { on_change(evt.value()) }
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use dioxus::prelude::*;
+use crate::app::FORMAT_OPTIONS;
+use crate::components::ToggleButton;
+
+#[component]
+pub fn FormatToggles(formats: Vec<String>, label: &'static str, on_toggle: EventHandler<String>) -> Element {
+    rsx! {
+        label { class: "w-full text-slate-200", {label} }
+        div { class: "w-full mt-4 mb-8 flex flex-row flex-wrap gap-2",
+            for (value , label) in FORMAT_OPTIONS.iter().skip(1) {
+                ToggleButton {
+                    label,
+                    is_active: formats.iter().any(|format| format == value),
+                    on_click: move |_| on_toggle(value.to_string())
+                }
+            }
+        }
+    }
+}
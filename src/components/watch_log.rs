@@ -0,0 +1,16 @@
+use dioxus::prelude::*;
+use crate::app::WatchEntry;
+
+#[component]
+pub fn WatchLog(entries: Vec<WatchEntry>) -> Element {
+    rsx! {
+        label { class: "w-full text-slate-200", "Watch log" }
+        ul { class: "w-full mt-4 mb-8 flex flex-col gap-2 text-sm",
+            for entry in entries.into_iter().rev() {
+                li { class: if entry.is_error { "text-red-500" } else { "text-slate-400" },
+                    {format!("{} : {}", entry.name, entry.message)}
+                }
+            }
+        }
+    }
+}
@@ -1,26 +1,201 @@
+use image::ImageError;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+/// Step of the pipeline a picture goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Load,
+    Resize,
+    Rotate,
+    Format,
+    Write,
+}
+
+/// What went wrong, keeping the error of the library that failed as the source.
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+    Io(Arc<std::io::Error>),
+    Image(Arc<ImageError>),
+    Avif(Arc<ravif::Error>),
+    Webp(Arc<EncoderError>),
+    Jpeg(Arc<EncoderError>),
+    Png(Arc<dyn std::error::Error + Send + Sync>),
+    InvalidSize { width: u32, height: u32 },
+    TooLarge { bytes: u64, max: u64 },
+    /// Best SSIM reached by the highest quality, under the target.
+    Unreachable { score: f64, target: f64 },
+    Unsupported(&'static str),
+    NoFormat,
+    Overwrite,
+    Cancelled,
+}
 
+/// Message of an encoder whose library has no error type of its own, a libwebp status or a mozjpeg panic.
+#[derive(Debug)]
+pub struct EncoderError(pub String);
 
+/// A failure of the pipeline along with the picture read and the file written when it happened.
 #[derive(Debug, Clone, PartialEq)]
-pub enum TransformationError{
-    IO(String),
-    Image(String),
-    Resize(String),
-    Rotate(String),
-    Format(String),
-    Metadata(String),
+pub struct TransformationError {
+    pub stage: Stage,
+    pub kind: ErrorKind,
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
 }
 
-impl std::fmt::Display for TransformationError {
+impl TransformationError {
+    pub fn new(stage: Stage, kind: impl Into<ErrorKind>) -> Self {
+        Self { stage, kind: kind.into(), input: None, output: None }
+    }
+
+    pub fn cancelled(stage: Stage) -> Self {
+        Self::new(stage, ErrorKind::Cancelled)
+    }
+
+    /// Sets the picture read, unless an inner step already did.
+    pub fn with_input(mut self, path: &Path) -> Self {
+        self.input.get_or_insert_with(|| path.to_path_buf());
+        self
+    }
+
+    /// Sets the file written, unless an inner step already did.
+    pub fn with_output(mut self, path: &Path) -> Self {
+        self.output.get_or_insert_with(|| path.to_path_buf());
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.kind, ErrorKind::Cancelled)
+    }
+
+    pub fn get_stage(&self) -> &'static str {
+        self.stage.as_str()
+    }
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Load => "load",
+            Stage::Resize => "resize",
+            Stage::Rotate => "rotate",
+            Stage::Format => "format",
+            Stage::Write => "write",
+        }
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TransformationError::IO(err) => write!(f, "IO Error: {}", err),
-            TransformationError::Image(err) => write!(f, "Image Error: {}", err),
-            TransformationError::Resize(err) => write!(f, "Resize Error: {}", err),
-            TransformationError::Rotate(err) => write!(f, "Rotate Error: {}", err),
-            TransformationError::Format(err) => write!(f, "Format Error: {}", err),
-            TransformationError::Metadata(err) => write!(f, "Metadata Error: {}", err),
+            ErrorKind::Io(err) => write!(f, "{}", err),
+            ErrorKind::Image(err) => write!(f, "{}", err),
+            ErrorKind::Avif(err) => write!(f, "{}", err),
+            ErrorKind::Webp(err) => write!(f, "{}", err),
+            ErrorKind::Jpeg(err) => write!(f, "{}", err),
+            ErrorKind::Png(err) => write!(f, "{}", err),
+            ErrorKind::InvalidSize { width, height } => write!(f, "Invalid size {}x{}", width, height),
+            ErrorKind::TooLarge { bytes, max } => write!(f, "Smallest output is {} bytes, over the max size of {} bytes", bytes, max),
+            ErrorKind::Unreachable { score, target } => write!(f, "Highest quality scores an SSIM of {:.4}, under the target of {}", score, target),
+            ErrorKind::Unsupported(err) => write!(f, "{}", err),
+            ErrorKind::NoFormat => write!(f, "No output format"),
+            ErrorKind::Overwrite => write!(f, "The output would replace the picture, set a prefix, a suffix or another destination"),
+            ErrorKind::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::fmt::Display for TransformationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error", self.stage)?;
+        if let Some(input) = &self.input {
+            write!(f, " on {}", input.display())?;
+        }
+        if let Some(output) = &self.output {
+            write!(f, " writing {}", output.display())?;
         }
+        write!(f, ": {}", self.kind)
     }
 }
 
+impl std::error::Error for TransformationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(err) => Some(err.as_ref()),
+            ErrorKind::Image(err) => Some(err.as_ref()),
+            ErrorKind::Avif(err) => Some(err.as_ref()),
+            ErrorKind::Webp(err) | ErrorKind::Jpeg(err) => Some(err.as_ref()),
+            ErrorKind::Png(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Errors only hold their message for comparison, the sources themselves are not comparable.
+impl PartialEq for ErrorKind {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.to_string() == other.to_string()
+    }
+}
+
+impl From<std::io::Error> for ErrorKind {
+    fn from(err: std::io::Error) -> Self {
+        ErrorKind::Io(Arc::new(err))
+    }
+}
+
+impl From<ImageError> for ErrorKind {
+    fn from(err: ImageError) -> Self {
+        ErrorKind::Image(Arc::new(err))
+    }
+}
+
+impl From<ravif::Error> for ErrorKind {
+    fn from(err: ravif::Error) -> Self {
+        ErrorKind::Avif(Arc::new(err))
+    }
+}
+
+impl From<oxipng::PngError> for ErrorKind {
+    fn from(err: oxipng::PngError) -> Self {
+        ErrorKind::Png(Arc::new(err))
+    }
+}
+
+impl From<png::EncodingError> for ErrorKind {
+    fn from(err: png::EncodingError) -> Self {
+        ErrorKind::Png(Arc::new(err))
+    }
+}
+
+impl std::fmt::Display for EncoderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for EncoderError {}
+
+impl From<ImageError> for TransformationError {
+    fn from(err: ImageError) -> Self {
+        let stage = match err {
+            ImageError::Encoding(_) => Stage::Format,
+            ImageError::IoError(_) => Stage::Write,
+            _ => Stage::Load,
+        };
+        Self::new(stage, err)
+    }
+}
+
+impl From<ravif::Error> for TransformationError {
+    fn from(err: ravif::Error) -> Self {
+        Self::new(Stage::Format, err)
+    }
+}
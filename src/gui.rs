@@ -0,0 +1,856 @@
+use crate::app::{Application, Cancel, Comparison, Estimate, Failure, MaxSize, Picture, Preset, Presets, Quality, ResizeType, SortOrder, Speed, Target, Thumbnails, Watch, WatchEntry};
+use crate::components::{Checkbox, CompareView, ErrorPanel, EstimatePanel, FileSelector, FormatToggles, Numbers, OrderByButton, Pictures, PreviewPane, ProgressBar, SettingButton, Texts, ToggleButton, TransformButton, Viewport, WatchButton, WatchLog};
+use crate::components::{Selectable, SelectableSetting};
+use dioxus::prelude::*;
+use image::ImageFormat;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+#[component]
+pub fn App() -> Element {
+    let mut app = use_signal(Application::default);
+    let mut watch = use_signal(|| None::<Watch>);
+    let mut cancel = use_signal(Cancel::default);
+    let mut estimate = use_signal(|| None::<Estimate>);
+    let mut is_estimating = use_signal(|| false);
+    let estimate_label = if is_estimating() { "Estimating..." } else { "Estimate output sizes" };
+    let mut presets = use_signal(|| Presets { list: Preset::list(), selected: String::new() });
+    let mut preset_name = use_signal(String::new);
+    let mut preset_error = use_signal(|| None::<String>);
+    let mut comparison = use_signal(|| None::<Comparison>);
+    let mut is_comparing = use_signal(|| false);
+    let mut compare_error = use_signal(|| None::<String>);
+    let mut section = use_signal(|| None::<Rc<MountedData>>);
+    let mut viewport = use_signal(Viewport::default);
+    let mut pending_thumbnails = use_signal(HashSet::<PathBuf>::new);
+
+    // Generates in the background the thumbnails of the rows the grid shows, as they scroll into view.
+    // Pictures whose thumbnail failed stay pending so they are not tried again.
+    let mut load_thumbnails = move |pictures: Vec<Picture>| {
+        let pictures = pictures
+            .into_iter()
+            .filter(|p| !pending_thumbnails.peek().contains(&p.path))
+            .collect::<Vec<_>>();
+        if pictures.is_empty() {
+            return;
+        }
+        pending_thumbnails.write().extend(pictures.iter().map(|p| p.path.clone()));
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            Thumbnails::default().get_all(&pictures, |path, result| {
+                if let Ok(thumbnail) = result {
+                    let _ = sender.send((path.to_path_buf(), thumbnail));
+                }
+            });
+        });
+        spawn(async move {
+            while let Some((path, thumbnail)) = receiver.recv().await {
+                pending_thumbnails.write().remove(&path);
+                app.with_mut(|a| a.on_thumbnail(&path, thumbnail));
+            }
+        });
+    };
+
+    // The grid renders the rows in view, it is measured again each time the pictures section scrolls.
+    let mut measure_viewport = move || {
+        if let Some(section) = section() {
+            spawn(async move {
+                if let Ok(rect) = section.get_client_rect().await {
+                    let scrolled = viewport.peek().scrolled + 1;
+                    viewport.set(Viewport { height: rect.size.height, scrolled });
+                }
+            });
+        }
+    };
+
+    // Runs the selected pictures, reporting each one back into the grid as it goes.
+    let mut start_transform = move || {
+        app.with_mut(|a| a.start_batch());
+        let mut _app = app.with(|a| a.batch());
+        let token = Cancel::default();
+        cancel.set(token.clone());
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        spawn(async move {
+            app.with_mut(|a| a.is_in_process = true);
+            let worker = tokio::task::spawn(async move {
+                let result = _app
+                    .transform_with_progress(&token, move |progress| {
+                        let _ = sender.send(progress);
+                    })
+                    .await;
+                // Only the manifest can fail the whole batch, the pictures report their own errors.
+                _app.manifest_error = result.err();
+                _app
+            });
+            // The channel closes once the worker is done with its sender.
+            while let Some(progress) = receiver.recv().await {
+                app.with_mut(|a| a.on_progress(progress));
+            }
+            if let Ok(_app) = worker.await {
+                app.with_mut(|a| {
+                    a.errors = _app.errors;
+                    a.manifest_error = _app.manifest_error;
+                });
+            }
+            app.with_mut(|a| a.is_in_process = false);
+            app.with_mut(|a| a.is_processed = true);
+        });
+    };
+
+    rsx! {
+        style { {include_str!("../public/output.css")} }
+        body { class: "dark h-screen w-screen overflow-hidden p-0 m-0 flex bg-slate-950",
+            section {
+                class: "w-full min-w-96 overflow-y-auto overflow-performance relative",
+                onmounted: move |evt| {
+                    section.set(Some(evt.data()));
+                    measure_viewport();
+                },
+                onscroll: move |_| measure_viewport(),
+                header { class: "w-full py-4 px-8 flex justify-end align-center gap-8 sticky top-0 left-0 bg-slate-950 z-20",
+                    if app.with(|a| a.progress.total > 0) {
+                        ProgressBar { progress: app.with(|a| a.progress.clone()) }
+                    }
+                    Checkbox {
+                        is_checked: app.with(|a| a.is_all_selected()),
+                        on_click: move |evt| {
+                            spawn(async move {
+                                if evt {
+                                    app.with_mut(|a| a.select_all())
+                                } else {
+                                    app.with_mut(|a| a.unselect_all())
+                                }
+                            });
+                        }
+                    }
+                    Selectable {
+                        options: app.with(|a| a.sort.field.clone()),
+                        label: "Sort by : ",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.sort.set_field(evt));
+                            spawn(async move {
+                                app.with_mut(|a| a.sort_pictures());
+                            });
+                        }
+                    }
+                    OrderByButton {
+                        is_asc: app.with(|a| a.sort.order == SortOrder::Asc),
+                        on_click: move |_| {
+                            app.with_mut(|a| a.sort.set_order());
+                            spawn(async move {
+                                app.with_mut(|a| a.sort_pictures());
+                            });
+                        }
+                    }
+                }
+                if app.with(|a| !a.errors.is_empty() || a.manifest_error.is_some()) {
+                    ErrorPanel {
+                        errors: app.with(|a| a.errors.clone()),
+                        manifest_error: app.with(|a| a.manifest_error.clone()),
+                        on_retry: move |_| {
+                            if app.with(|a| a.is_in_process || !a.has_failed()) {
+                                return;
+                            }
+                            app.with_mut(|a| a.select_failed());
+                            start_transform();
+                        },
+                        on_dismiss: move |_| app.with_mut(|a| {
+                            a.errors.clear();
+                            a.manifest_error = None;
+                        })
+                    }
+                }
+                if let Some(result) = estimate() {
+                    EstimatePanel {
+                        estimate: result,
+                        on_dismiss: move |_| estimate.set(None)
+                    }
+                }
+                if app.with(|a| a.focused.is_some()) {
+                    PreviewPane { app }
+                }
+                if let Some(err) = compare_error() {
+                    p { class: "mx-8 text-sm text-red-500", {err} }
+                }
+                Pictures {
+                    app,
+                    viewport,
+                    on_missing_thumbnails: move |pictures| load_thumbnails(pictures),
+                    on_compare: move |index| {
+                        if is_comparing() {
+                            return;
+                        }
+                        is_comparing.set(true);
+                        compare_error.set(None);
+                        let (picture, job) = app.with(|a| a.compare_job(index));
+                        spawn(async move {
+                            let compare = move || {
+                                Comparison::new(&picture, &job).map_err(|error| Failure { name: picture.get_name(), error })
+                            };
+                            match tokio::task::spawn_blocking(compare).await {
+                                Ok(Ok(result)) => comparison.set(Some(result)),
+                                Ok(Err(failure)) => compare_error.set(Some(failure.to_string())),
+                                Err(err) => compare_error.set(Some(err.to_string())),
+                            }
+                            is_comparing.set(false);
+                        });
+                    }
+                }
+            }
+            section { class: "w-1/4 min-w-96 h-screen p-8 m-0 sticky top-0 bg-gray-900 overflow-y-auto",
+                FileSelector {
+                    value: app.with(|a| a.paths.source.clone()),
+                    label: "Source path",
+                    on_click: move |_| {
+                        let files = rfd::FileDialog::new()
+                            .set_title("Select a source directory")
+                            .set_directory(".")
+                            .pick_folder();
+                        spawn(async move {
+                            app.with_mut(|a| a.set_source_path(files));
+                        });
+                    },
+                    on_change: move |evt| {
+                        let path = PathBuf::from(evt);
+                        if path.is_dir() {
+                            spawn(async move {
+                                app.with_mut(|a| a.set_source_path(Some(path)));
+                            });
+                        }
+                    }
+                }
+                FileSelector {
+                    value: app.with(|a| a.paths.destination.clone()),
+                    label: "Output path",
+                    on_click: move |_| {
+                        let files = rfd::FileDialog::new()
+                            .set_title("Select an output directory")
+                            .set_directory(".")
+                            .pick_folder();
+                        app.with_mut(|a| a.set_destination_path(files));
+                    },
+                    on_change: move |evt| {
+                        let path = PathBuf::from(evt);
+                        if path.is_dir() {
+                            app.with_mut(|a| a.set_destination_path(Some(path)));
+                        }
+                    }
+                }
+
+                SelectableSetting {
+                    options: app.with(|a| a.scan.clone()),
+                    label: "Subfolders",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.scan.set_recursive(evt));
+                        spawn(async move {
+                            app.with_mut(|a| a.rescan());
+                        });
+                    }
+                }
+                if app.with(|a| a.scan.is_recursive) {
+                    Numbers {
+                        value: app.with(|a| a.scan.depth),
+                        min: 1,
+                        max: 64,
+                        step: 1,
+                        label: "Depth",
+                        on_change: move |evt: String| {
+                            let value = evt.parse::<u32>().unwrap_or(1);
+                            app.with_mut(|a| a.scan.depth = value);
+                            spawn(async move {
+                                app.with_mut(|a| a.rescan());
+                            });
+                        }
+                    }
+                    SelectableSetting {
+                        options: app.with(|a| a.scan.symlinks.clone()),
+                        label: "Symbolic links",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.scan.set_symlinks(evt));
+                            spawn(async move {
+                                app.with_mut(|a| a.rescan());
+                            });
+                        }
+                    }
+                }
+
+                Texts {
+                    value: app.with(|a| a.filters.get_patterns()),
+                    label: "Filters",
+                    placeholder: "**/*.{jpg,png} !**/thumbs/**",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.filters.set_patterns(evt));
+                        spawn(async move {
+                            app.with_mut(|a| a.rescan());
+                        });
+                    }
+                }
+                if let Err(err) = app.with(|a| a.filters.matcher().map(|_| ())) {
+                    p { class: "w-full -mt-4 mb-8 text-sm text-red-500", {err.to_string()} }
+                }
+
+                SelectableSetting {
+                    options: presets(),
+                    label: "Preset",
+                    on_change: move |evt: String| {
+                        let preset = presets.with(|p| p.list.iter().find(|preset| preset.name == evt).cloned());
+                        if let Some(preset) = preset {
+                            app.with_mut(|a| preset.apply(a));
+                            preset_name.set(preset.name);
+                        }
+                        presets.with_mut(|p| p.selected = evt);
+                    }
+                }
+                Texts {
+                    value: preset_name(),
+                    label: "Preset name",
+                    placeholder: "Web thumbnails",
+                    on_change: move |evt| preset_name.set(evt)
+                }
+                div { class: "w-full flex flex-row gap-2",
+                    SettingButton {
+                        label: "Save",
+                        on_click: move |_| {
+                            let preset = Preset::from_app(&preset_name(), &app.read());
+                            match preset.save() {
+                                Ok(_) => {
+                                    presets.set(Presets { list: Preset::list(), selected: preset.name });
+                                    preset_error.set(None);
+                                }
+                                Err(err) => preset_error.set(Some(err.to_string())),
+                            }
+                        }
+                    }
+                    SettingButton {
+                        label: "Import",
+                        on_click: move |_| {
+                            let file = rfd::FileDialog::new()
+                                .set_title("Import a preset")
+                                .add_filter("Preset", &["toml"])
+                                .pick_file();
+                            let Some(file) = file else { return };
+                            match Preset::import(&file) {
+                                Ok(preset) => {
+                                    app.with_mut(|a| preset.apply(a));
+                                    preset_name.set(preset.name.clone());
+                                    presets.set(Presets { list: Preset::list(), selected: preset.name });
+                                    preset_error.set(None);
+                                }
+                                Err(err) => preset_error.set(Some(err.to_string())),
+                            }
+                        }
+                    }
+                    SettingButton {
+                        label: "Export",
+                        on_click: move |_| {
+                            let preset = Preset::from_app(&preset_name(), &app.read());
+                            let file = rfd::FileDialog::new()
+                                .set_title("Export the preset")
+                                .add_filter("Preset", &["toml"])
+                                .set_file_name(format!("{}.toml", preset.name))
+                                .save_file();
+                            if let Some(file) = file {
+                                if let Err(err) = preset.write(&file) {
+                                    preset_error.set(Some(err.to_string()));
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Some(err) = preset_error() {
+                    p { class: "w-full -mt-4 mb-8 text-sm text-red-500", {err} }
+                }
+
+                SelectableSetting {
+                    options: app.with(|a| a.resize.clone()),
+                    label: "Resize",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.resize.set_resize_type(evt));
+                    }
+                }
+                if app.with(|a| {
+                    a.resize.resize_type != Some(ResizeType::Exact)
+                        && app.with(|a| a.resize.resize_type.is_some())
+                })
+                {
+                    SelectableSetting {
+                        options: app.with(|a| a.resize.method.clone()),
+                        label: "Method",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.resize.set_method(evt));
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.resize.width),
+                        min: 0,
+                        max: 8192,
+                        step: 1,
+                        label: "Width",
+                        on_change: move |evt: String| {
+                            let value = evt.parse::<u32>().unwrap_or(0);
+                            app.with_mut(|a| a.resize.width = value);
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.resize.height),
+                        min: 0,
+                        max: 8192,
+                        step: 1,
+                        label: "Height",
+                        on_change: move |evt: String| {
+                            let value = evt.parse::<u32>().unwrap_or(0);
+                            app.with_mut(|a| a.resize.height = value);
+                        }
+                    }
+                }
+                SelectableSetting {
+                    options: app.with(|a| a.format.clone()),
+                    label: "Format",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.format.set_format(evt));
+                    }
+                }
+
+                if app.with(|a| a.format.is_lossy() && (a.format.target.is_none() || !a.format.can_target())) {
+                    div {
+                        label { class: "w-full p-4 text-slate-200 my-4",
+                            {format!("Quality : {}", app.with(|a| a.format.quality.value))}
+                        }
+                        input {
+                            r#type: "range",
+                            class: "w-full p-4",
+                            min: 0,
+                            max: 100,
+                            value: app.with(|a| a.format.quality.value).to_string(),
+                            onchange: move |evt| {
+                                let value = evt.value();
+                                app.with_mut(|a| a.format.quality = Quality::from(value));
+                            }
+                        }
+                    }
+                }
+                if app.with(|a| a.format.image == Some(ImageFormat::Jpeg)) {
+                    SelectableSetting {
+                        options: app.with(|a| a.format.jpeg.subsampling),
+                        label: "Chroma subsampling",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.format.jpeg.set_subsampling(evt));
+                        }
+                    }
+                    div { class: "w-full mb-8 flex flex-row gap-2 flex-wrap",
+                        ToggleButton {
+                            label: "Progressive",
+                            is_active: app.with(|a| a.format.jpeg.progressive),
+                            on_click: move |_| app.with_mut(|a| a.format.jpeg.progressive = !a.format.jpeg.progressive)
+                        }
+                        ToggleButton {
+                            label: "Optimized Huffman tables",
+                            is_active: app.with(|a| a.format.jpeg.optimize_coding),
+                            on_click: move |_| app.with_mut(|a| a.format.jpeg.optimize_coding = !a.format.jpeg.optimize_coding)
+                        }
+                        ToggleButton {
+                            label: "Trellis quantization",
+                            is_active: app.with(|a| a.format.jpeg.trellis),
+                            on_click: move |_| app.with_mut(|a| a.format.jpeg.trellis = !a.format.jpeg.trellis)
+                        }
+                    }
+                }
+                if app.with(|a| a.format.is_palette()) {
+                    div { class: "w-full mb-8 flex flex-row",
+                        ToggleButton {
+                            label: "Dithering",
+                            is_active: app.with(|a| a.format.png.dithering),
+                            on_click: move |_| app.with_mut(|a| a.format.png.dithering = !a.format.png.dithering)
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.format.png.min_quality),
+                        min: 0,
+                        max: 100,
+                        step: 1,
+                        label: "Lowest quality of the palette, in SSIM percent",
+                        on_change: move |evt: String| {
+                            let min_quality = evt.parse::<u8>().unwrap_or(0).min(100);
+                            app.with_mut(|a| a.format.png.min_quality = min_quality);
+                        }
+                    }
+                }
+                if app.with(|a| a.format.image == Some(ImageFormat::Png)) {
+                    div { class: "w-full mb-8 flex flex-row",
+                        ToggleButton {
+                            label: "Optimise losslessly",
+                            is_active: app.with(|a| a.format.png.optimize),
+                            on_click: move |_| app.with_mut(|a| a.format.png.optimize = !a.format.png.optimize)
+                        }
+                    }
+                    if app.with(|a| a.format.png.optimize) {
+                        div {
+                            label { class: "w-full p-4 text-slate-200 my-4",
+                                {format!("Effort : {}", app.with(|a| a.format.png.effort))}
+                            }
+                            input {
+                                r#type: "range",
+                                class: "w-full p-4",
+                                min: 0,
+                                max: 6,
+                                value: app.with(|a| a.format.png.effort).to_string(),
+                                onchange: move |evt| {
+                                    let value = evt.value();
+                                    app.with_mut(|a| a.format.png.set_effort(value));
+                                }
+                            }
+                        }
+                        div { class: "w-full mb-8 flex flex-row gap-2 flex-wrap",
+                            ToggleButton {
+                                label: "Reduce colours",
+                                is_active: app.with(|a| a.format.png.reduce),
+                                on_click: move |_| app.with_mut(|a| a.format.png.reduce = !a.format.png.reduce)
+                            }
+                            ToggleButton {
+                                label: "Strip metadata",
+                                is_active: app.with(|a| a.format.png.strip),
+                                on_click: move |_| app.with_mut(|a| a.format.png.strip = !a.format.png.strip)
+                            }
+                            ToggleButton {
+                                label: "Zopfli",
+                                is_active: app.with(|a| a.format.png.zopfli),
+                                on_click: move |_| app.with_mut(|a| a.format.png.zopfli = !a.format.png.zopfli)
+                            }
+                        }
+                    }
+                }
+                if app.with(|a| a.format.image == Some(ImageFormat::WebP)) {
+                    div { class: "w-full mb-8 flex flex-row gap-2 flex-wrap",
+                        ToggleButton {
+                            label: "Lossless",
+                            is_active: app.with(|a| a.format.webp.lossless),
+                            on_click: move |_| app.with_mut(|a| a.format.webp.lossless = !a.format.webp.lossless)
+                        }
+                        ToggleButton {
+                            label: "Sharp YUV",
+                            is_active: app.with(|a| a.format.webp.sharp_yuv),
+                            on_click: move |_| app.with_mut(|a| a.format.webp.sharp_yuv = !a.format.webp.sharp_yuv)
+                        }
+                    }
+                    div {
+                        label { class: "w-full p-4 text-slate-200 my-4",
+                            {format!("Method : {}", app.with(|a| a.format.webp.method))}
+                        }
+                        input {
+                            r#type: "range",
+                            class: "w-full p-4",
+                            min: 0,
+                            max: 6,
+                            value: app.with(|a| a.format.webp.method).to_string(),
+                            onchange: move |evt| {
+                                let value = evt.value();
+                                app.with_mut(|a| a.format.webp.set_method(value));
+                            }
+                        }
+                    }
+                    if app.with(|a| a.format.webp.lossless) {
+                        Numbers {
+                            value: app.with(|a| a.format.webp.lossless_effort),
+                            min: 0,
+                            max: 100,
+                            step: 1,
+                            label: "Lossless effort",
+                            on_change: move |evt: String| {
+                                let lossless_effort = evt.parse::<u8>().unwrap_or(75).min(100);
+                                app.with_mut(|a| a.format.webp.lossless_effort = lossless_effort);
+                            }
+                        }
+                        Numbers {
+                            value: app.with(|a| a.format.webp.near_lossless),
+                            min: 0,
+                            max: 100,
+                            step: 1,
+                            label: "Near lossless, 100 being off",
+                            on_change: move |evt: String| {
+                                let near_lossless = evt.parse::<u8>().unwrap_or(100).min(100);
+                                app.with_mut(|a| a.format.webp.near_lossless = near_lossless);
+                            }
+                        }
+                    }
+                    if app.with(|a| !a.format.webp.lossless) {
+                        Texts {
+                            value: app.with(|a| a.format.webp.get_target_size()),
+                            label: "Target size",
+                            placeholder: "50 KB",
+                            on_change: move |evt| {
+                                app.with_mut(|a| a.format.webp.set_target_size(evt));
+                            }
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.format.webp.alpha_quality),
+                        min: 0,
+                        max: 100,
+                        step: 1,
+                        label: "Alpha quality",
+                        on_change: move |evt: String| {
+                            let alpha_quality = evt.parse::<u8>().unwrap_or(100).min(100);
+                            app.with_mut(|a| a.format.webp.alpha_quality = alpha_quality);
+                        }
+                    }
+                }
+                if app.with(|a| a.format.image == Some(ImageFormat::Avif)) {
+                    div {
+                        label { class: "w-full p-4 text-slate-200 my-4",
+                            {format!("Speed : {}", app.with(|a| a.format.speed.value))}
+                        }
+                        input {
+                            r#type: "range",
+                            class: "w-full p-4",
+                            min: 1,
+                            max: 10,
+                            value: app.with(|a| a.format.speed.value).to_string(),
+                            onchange: move |evt| {
+                                let value = evt.value();
+                                app.with_mut(|a| a.format.speed = Speed::from(value));
+                            }
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.format.avif.alpha_quality),
+                        min: 1,
+                        max: 100,
+                        step: 1,
+                        label: "Alpha quality",
+                        on_change: move |evt: String| {
+                            let alpha_quality = evt.parse::<u8>().unwrap_or(80).clamp(1, 100);
+                            app.with_mut(|a| a.format.avif.alpha_quality = alpha_quality);
+                        }
+                    }
+                    SelectableSetting {
+                        options: app.with(|a| a.format.avif.alpha_mode),
+                        label: "Transparent colours",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.format.avif.set_alpha_mode(evt));
+                        }
+                    }
+                    SelectableSetting {
+                        options: app.with(|a| a.format.avif.color_model),
+                        label: "Colour model",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.format.avif.set_color_model(evt));
+                        }
+                    }
+                    div { class: "w-full mb-8 flex flex-row",
+                        ToggleButton {
+                            label: "10-bit",
+                            is_active: app.with(|a| a.format.avif.ten_bit),
+                            on_click: move |_| app.with_mut(|a| a.format.avif.ten_bit = !a.format.avif.ten_bit)
+                        }
+                    }
+                    Numbers {
+                        value: app.with(|a| a.format.avif.threads),
+                        min: 0,
+                        max: 64,
+                        step: 1,
+                        label: "Threads, 0 using all of them",
+                        on_change: move |evt: String| {
+                            let threads = evt.parse::<u8>().unwrap_or(0);
+                            app.with_mut(|a| a.format.avif.threads = threads);
+                        }
+                    }
+                }
+
+                if app.with(|a| a.format.can_target()) {
+                    div { class: "w-full mb-8 flex flex-row",
+                        ToggleButton {
+                            label: "Pick the quality reaching a perceptual target",
+                            is_active: app.with(|a| a.format.target.is_some()),
+                            on_click: move |_| {
+                                app.with_mut(|a| {
+                                    a.format.target = match a.format.target {
+                                        Some(_) => None,
+                                        None => Some(Target::default()),
+                                    }
+                                });
+                            }
+                        }
+                    }
+                }
+                if let Some(target) = app.with(|a| a.format.target.clone().filter(|_| a.format.can_target())) {
+                    Numbers {
+                        value: target.ssim,
+                        min: 0.9,
+                        max: 1.0,
+                        step: 0.001,
+                        label: "Target SSIM",
+                        on_change: move |evt: String| {
+                            let ssim = evt.parse::<f64>().unwrap_or(Target::default().ssim).clamp(0.0, 1.0);
+                            app.with_mut(|a| a.format.target = Some(Target { ssim }));
+                        }
+                    }
+                }
+                div { class: "w-full mb-8 flex flex-row",
+                    ToggleButton {
+                        label: "Fit each output under a max size",
+                        is_active: app.with(|a| a.format.max_size.is_some()),
+                        on_click: move |_| {
+                            app.with_mut(|a| {
+                                a.format.max_size = match a.format.max_size {
+                                    Some(_) => None,
+                                    None => Some(MaxSize::default()),
+                                }
+                            });
+                        }
+                    }
+                }
+                if let Some(max_size) = app.with(|a| a.format.max_size.clone()) {
+                    Texts {
+                        value: app.with(|a| a.format.get_max_size()),
+                        label: "Max size",
+                        placeholder: "200 KB",
+                        on_change: move |evt| {
+                            app.with_mut(|a| a.format.set_max_size(evt));
+                        }
+                    }
+                    if app.with(|a| a.format.is_lossy()) {
+                        Numbers {
+                            value: max_size.min_quality,
+                            min: 0,
+                            max: 100,
+                            step: 1,
+                            label: "Lowest quality",
+                            on_change: move |evt: String| {
+                                let value = evt.parse::<u8>().unwrap_or(30).min(100);
+                                app.with_mut(|a| {
+                                    if let Some(max_size) = a.format.max_size.as_mut() {
+                                        max_size.min_quality = value;
+                                    }
+                                });
+                            }
+                        }
+                    }
+                    div { class: "w-full mb-8 flex flex-row",
+                        ToggleButton {
+                            label: "Downscale what does not fit",
+                            is_active: max_size.downscale,
+                            on_click: move |_| {
+                                app.with_mut(|a| {
+                                    if let Some(max_size) = a.format.max_size.as_mut() {
+                                        max_size.downscale = !max_size.downscale;
+                                    }
+                                });
+                            }
+                        }
+                    }
+                }
+                Texts {
+                    value: app.with(|a| a.variants.get_widths()),
+                    label: "Variant widths",
+                    placeholder: "480, 960, 1920",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.variants.set_widths(evt));
+                    }
+                }
+                FormatToggles {
+                    formats: app.with(|a| a.variants.formats.clone()),
+                    label: "Variant formats",
+                    on_toggle: move |evt| {
+                        app.with_mut(|a| a.variants.toggle_format(evt));
+                    }
+                }
+                div { class: "w-full mb-8 flex flex-row",
+                    ToggleButton {
+                        label: "Write manifest.json and HTML snippets",
+                        is_active: app.with(|a| a.write_manifest),
+                        on_click: move |_| {
+                            app.with_mut(|a| a.write_manifest = !a.write_manifest);
+                        }
+                    }
+                }
+                SelectableSetting {
+                    options: app.with(|a| a.rotate.clone()),
+                    label: "Rotate",
+                    on_change: move |evt| {
+                        app.with_mut(|a| a.rotate.set_angle(evt));
+                    }
+                }
+                SettingButton {
+                    label: estimate_label,
+                    on_click: move |_| {
+                        // A dry run only reads the source, the destination does not have to be picked yet.
+                        if is_estimating() || app.with(|a| !a.paths.source.is_dir()) {
+                            return;
+                        }
+                        is_estimating.set(true);
+                        let _app = app.with(|a| a.batch());
+                        spawn(async move {
+                            // Encoding runs in memory only, nothing is written to the destination.
+                            if let Ok(result) = tokio::task::spawn_blocking(move || _app.estimate()).await {
+                                estimate.set(Some(result));
+                            }
+                            is_estimating.set(false);
+                        });
+                    }
+                }
+                TransformButton {
+                    is_disabled: app.with(|a| !a.paths.is_valid()) || app.with(|a| a.is_in_process),
+                    on_click: move |_| start_transform()
+                }
+                if app.with(|a| a.is_in_process) {
+                    SettingButton {
+                        label: "Cancel",
+                        on_click: move |_| {
+                            cancel.read().cancel();
+                            app.with_mut(|a| a.progress.is_cancelled = true);
+                        }
+                    }
+                }
+                WatchButton {
+                    is_watching: app.with(|a| a.is_watching),
+                    is_disabled: app.with(|a| !a.paths.is_valid()),
+                    on_click: move |_| {
+                        if watch.with_mut(|w| w.take()).is_some() {
+                            app.with_mut(|a| a.is_watching = false);
+                            return;
+                        }
+
+                        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+                        let started = Watch::start(&app.read(), move |event| {
+                            let _ = sender.send(event);
+                        });
+                        match started {
+                            Ok(handle) => {
+                                watch.set(Some(handle));
+                                app.with_mut(|a| a.is_watching = true);
+                                spawn(async move {
+                                    while let Some(event) = receiver.recv().await {
+                                        app.with_mut(|a| a.on_watch_event(event));
+                                    }
+                                });
+                            }
+                            Err(err) => {
+                                app.with_mut(|a| a.watch_log.push(WatchEntry {
+                                    name: a.paths.source.display().to_string(),
+                                    message: err.to_string(),
+                                    is_error: true,
+                                }));
+                            }
+                        }
+                    }
+                }
+                if app.with(|a| !a.watch_log.is_empty()) {
+                    WatchLog { entries: app.with(|a| a.watch_log.clone()) }
+                }
+            }
+            if is_comparing() {
+                div { class: "fixed inset-0 z-50 bg-slate-950 flex justify-center items-center text-slate-200",
+                    "Encoding the comparison..."
+                }
+            }
+            if let Some(result) = comparison() {
+                CompareView {
+                    comparison: result,
+                    on_close: move |_| comparison.set(None)
+                }
+            }
+        }
+    }
+}
@@ -2,10 +2,6 @@ use dioxus::desktop::{Config, WindowBuilder};
 use dioxus::prelude::*;
 use picturust_dx::App;
 
-mod error;
-mod components;
-mod app;
-
 fn main() {
 
     LaunchBuilder::new()